@@ -0,0 +1,404 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use serde_jsonc::Value;
+
+use crate::config::{parse_value, Config, ConfigError, Format};
+use crate::fs::expand;
+use crate::jsonc::{join_key, spans};
+
+/// Config issue found by `check-config`
+/// position (line, column) is known for parse errors, and for any issue in JSONC files
+pub struct Issue {
+    pub file: String,
+    pub position: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.position {
+            Some((line, column)) => write!(f, "{}:{}:{}: {}", self.file, line, column, self.message),
+            None => write!(f, "{}: {}", self.file, self.message),
+        }
+    }
+}
+
+/// checks config files (and files they import), returns all issues found
+pub fn check_config(paths: &[String]) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut checked = HashSet::new();
+    for path in paths {
+        check_file(Path::new(path), &mut issues, &mut checked);
+    }
+    issues
+}
+
+/// collects issues of a single config file, positions of issues are looked up by path of the value
+struct FileCheck<'a> {
+    file: String,
+    spans: HashMap<String, (usize, usize)>,
+    issues: &'a mut Vec<Issue>,
+}
+
+impl<'a> FileCheck<'a> {
+    /// reports issue at the value's position, or at the position of its closest parent if value is missing
+    fn report(&mut self, key: &str, message: impl Into<String>) {
+        let mut key = key;
+        let position = loop {
+            if let Some(position) = self.spans.get(key) {
+                break Some(*position);
+            }
+            match key.rsplit_once('.') {
+                Some((parent, _)) => key = parent,
+                None => break self.spans.get("").copied(),
+            }
+        };
+        self.issues.push(Issue {
+            file: self.file.clone(),
+            position,
+            message: message.into(),
+        });
+    }
+
+    fn check_regexes(&mut self, key: &str, patterns: &[String]) {
+        for (i, pattern) in patterns.iter().enumerate() {
+            if let Err(err) = Regex::new(pattern) {
+                // syntax errors are multiline with the pattern drawn above, keep only the description
+                let err = err.to_string();
+                let err = err
+                    .lines()
+                    .last()
+                    .unwrap_or_default()
+                    .trim_start_matches("error: ");
+                let message = format!("invalid regex {:?}: {}", pattern, err);
+                self.report(&format!("{}.pattern.{}", key, i), message);
+            }
+        }
+    }
+
+    /// checks that env vars used in the path are set and (if `must_exist`) that the path exists
+    fn check_path(&mut self, key: &str, path: &str, must_exist: bool) {
+        match expand(path) {
+            Ok(expanded) if must_exist && !Path::new(&expanded).exists() => {
+                self.report(key, format!("path {} does not exist", expanded))
+            }
+            Ok(_) => {}
+            Err(err) => self.report(key, format!("{} in path {}", err, path)),
+        }
+    }
+}
+
+fn check_file(path: &Path, issues: &mut Vec<Issue>, checked: &mut HashSet<PathBuf>) {
+    let file = path.display().to_string();
+    let start = issues.len();
+    let mut check = FileCheck {
+        file: file.clone(),
+        spans: HashMap::new(),
+        issues,
+    };
+    if !checked.insert(path.canonicalize().unwrap_or(path.to_path_buf())) {
+        return;
+    }
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) => {
+            check.report("", format!("could not read config: {}", err));
+            return;
+        }
+    };
+
+    // TOML and YAML are converted to JSON value first, JSONC is parsed directly to keep error positions
+    let format = Format::from_path(path);
+    let value = match format {
+        Format::Json => None,
+        format => match parse_value(&text, format) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                let (position, message) = parse_error(&text, err);
+                check.issues.push(Issue {
+                    file,
+                    position,
+                    message,
+                });
+                return;
+            }
+        },
+    };
+
+    // parse, collecting keys that are not part of the config schema
+    let mut unknown_keys = vec![];
+    let parsed: Result<Config, _> = match &value {
+        None => serde_ignored::deserialize(&mut serde_jsonc::Deserializer::from_str(&text), |p| {
+            unknown_keys.push(path_key(&p))
+        }),
+        Some(value) => serde_ignored::deserialize(value, |p| unknown_keys.push(path_key(&p))),
+    };
+    let config = match parsed {
+        Ok(config) => config,
+        Err(err) => {
+            let message = err.to_string();
+            check.issues.push(Issue {
+                file,
+                position: (err.line() > 0).then_some((err.line(), err.column())),
+                message: message
+                    .rsplit_once(" at line ")
+                    .map(|m| m.0)
+                    .unwrap_or(&message)
+                    .to_owned(),
+            });
+            return;
+        }
+    };
+    if format == Format::Json {
+        check.spans = spans(&text).positions;
+    }
+
+    for key in unknown_keys.iter() {
+        let name = key.rsplit('.').next().unwrap_or(key);
+        check.report(key, format!("unknown key `{}`", name));
+    }
+
+    check.check_regexes("markers", &config.markers.pattern);
+    check.check_regexes("ignore", &config.ignore.pattern);
+    for (i, entry) in config.include.iter().enumerate() {
+        for (j, path) in entry.paths.iter().enumerate() {
+            check.check_path(&format!("include.{}.paths.{}", i, j), path, true);
+        }
+        check.check_regexes(&format!("include.{}.markers", i), &entry.markers.pattern);
+        check.check_regexes(&format!("include.{}.ignore", i), &entry.ignore.pattern);
+    }
+
+    let mut session_names = HashSet::new();
+    for (i, session) in config.sessions.iter().enumerate() {
+        if !session_names.insert(session.name.as_str()) {
+            check.report(
+                &format!("sessions.{}.name", i),
+                format!("duplicate session name {}", session.name),
+            );
+        }
+        for (j, group) in session.groups.iter().enumerate() {
+            if config.sessions.iter().any(|s| &s.name == group) {
+                check.report(
+                    &format!("sessions.{}.groups.{}", i, j),
+                    format!(
+                        "group {} has the same name as a session, `start {}` starts only the session",
+                        group, group
+                    ),
+                );
+            }
+        }
+        for (j, window) in session.windows.iter().enumerate() {
+            check.check_path(&format!("sessions.{}.windows.{}", i, j), window, true);
+        }
+    }
+
+    let mut unknown_dependencies = false;
+    for (i, session) in config.sessions.iter().enumerate() {
+        for (j, dependency) in session.depends_on.iter().enumerate() {
+            if !session_names.contains(dependency.as_str()) {
+                unknown_dependencies = true;
+                check.report(
+                    &format!("sessions.{}.depends_on.{}", i, j),
+                    format!("unknown session {}", dependency),
+                );
+            }
+        }
+    }
+    if !unknown_dependencies {
+        let names = config
+            .sessions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect::<Vec<&str>>();
+        if let Err(err) = config.start_order(&names) {
+            check.report("sessions", err.to_string());
+        }
+    }
+
+    for (i, template) in config.templates.iter().enumerate() {
+        if let Some(path) = &template.path {
+            check.check_path(&format!("templates.{}.path", i), path, true);
+        }
+    }
+
+    // check imported files
+    let imports = match value {
+        Some(value) => value,
+        None => serde_jsonc::from_str::<Value>(&text).unwrap_or(Value::Null),
+    };
+    let imports = imports.get("imports").cloned().unwrap_or(Value::Null);
+    let dir = path.parent().unwrap_or(Path::new("/"));
+    let mut imported = vec![];
+    for (i, import) in imports.as_array().into_iter().flatten().enumerate() {
+        let key = format!("imports.{}", i);
+        let Some(import) = import.as_str() else {
+            check.report(&key, "import should be a path string");
+            continue;
+        };
+        match expand(import) {
+            Ok(import) if dir.join(&import).is_file() => imported.push(dir.join(import)),
+            Ok(import) => check.report(&key, format!("imported file {} does not exist", import)),
+            Err(err) => check.report(&key, format!("{} in path {}", err, import)),
+        }
+    }
+    check.issues[start..].sort_by_key(|issue| issue.position);
+    for import in imported {
+        check_file(&import, check.issues, checked);
+    }
+}
+
+/// position and message of TOML/YAML parse error
+fn parse_error(text: &str, err: ConfigError) -> (Option<(usize, usize)>, String) {
+    match err {
+        ConfigError::ParseToml(err) => {
+            let position = err.span().map(|span| {
+                let before = &text[..span.start];
+                let column = before.rsplit('\n').next().unwrap_or_default().chars().count() + 1;
+                (before.matches('\n').count() + 1, column)
+            });
+            (position, err.message().trim_end().replace('\n', ", "))
+        }
+        ConfigError::ParseYaml(err) => {
+            let position = err.location().map(|l| (l.line(), l.column()));
+            let message = err.to_string();
+            let message = message.split_once(" at line ").map(|m| m.0).unwrap_or(&message);
+            (position, message.to_owned())
+        }
+        err => (None, err.to_string()),
+    }
+}
+
+/// converts path reported by serde_ignored to the key format used by [`crate::jsonc::Spans`]
+fn path_key(path: &serde_ignored::Path) -> String {
+    match path {
+        serde_ignored::Path::Root => String::new(),
+        serde_ignored::Path::Seq { parent, index } => join_key(&path_key(parent), &index.to_string()),
+        serde_ignored::Path::Map { parent, key } => join_key(&path_key(parent), key),
+        serde_ignored::Path::Some { parent }
+        | serde_ignored::Path::NewtypeStruct { parent }
+        | serde_ignored::Path::NewtypeVariant { parent } => path_key(parent),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// writes files into a fresh temp dir, checks the first one and renders issues with the dir stripped
+    fn check(files: &[(&str, &str)]) -> Vec<String> {
+        let dir = std::env::temp_dir().join(format!("pfp-check-test-{}-{}", std::process::id(), files[0].0));
+        fs::create_dir_all(&dir).unwrap();
+        for (name, text) in files {
+            fs::write(dir.join(name), text).unwrap();
+        }
+        let issues = check_config(&[dir.join(files[0].0).to_string_lossy().to_string()]);
+        fs::remove_dir_all(&dir).unwrap();
+        let prefix = format!("{}/", dir.display());
+        issues
+            .iter()
+            .map(|issue| issue.to_string().replace(&prefix, ""))
+            .collect()
+    }
+
+    #[test]
+    fn report_falls_back_to_parent_position() {
+        let text = "{\n  \"sessions\": [\n    { \"name\": \"a\", \"windows\": [] }\n  ]\n}";
+        let mut issues = vec![];
+        let mut check = FileCheck {
+            file: "config.json".to_owned(),
+            spans: spans(text).positions,
+            issues: &mut issues,
+        };
+        check.report("sessions.0.windows.3", "missing window");
+        check.report("sessions.0.ready", "missing section");
+        check.report("nope", "missing root key");
+        let rendered = issues.iter().map(Issue::to_string).collect::<Vec<String>>();
+        assert_eq!(
+            rendered,
+            [
+                "config.json:3:20: missing window",
+                "config.json:3:5: missing section",
+                "config.json:1:1: missing root key",
+            ]
+        );
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let text = r#"{
+  "markers": [".git"],
+  "ignore": { "exact": ["target"], "bogus": true },
+  "include": [{ "paths": ["/"], "markers": ["go.mod"], "depht": 2 }],
+  "extra": 1
+}"#;
+        assert_eq!(
+            check(&[("unknown.jsonc", text)]),
+            [
+                "unknown.jsonc:3:36: unknown key `bogus`",
+                "unknown.jsonc:4:56: unknown key `depht`",
+                "unknown.jsonc:5:3: unknown key `extra`",
+            ]
+        );
+    }
+
+    #[test]
+    fn sessions() {
+        let text = r#"{
+  "sessions": [
+    { "name": "a", "windows": ["/"], "groups": ["b"] },
+    { "name": "a", "windows": ["/"] },
+    { "name": "b", "windows": ["/"], "depends_on": ["c"] }
+  ]
+}"#;
+        assert_eq!(
+            check(&[("sessions.json", text)]),
+            [
+                "sessions.json:3:49: group b has the same name as a session, `start b` starts only the session",
+                "sessions.json:4:7: duplicate session name a",
+                "sessions.json:5:53: unknown session c",
+            ]
+        );
+    }
+
+    #[test]
+    fn dependency_cycle() {
+        let text = r#"{
+  "sessions": [
+    { "name": "a", "windows": ["/"], "depends_on": ["b"] },
+    { "name": "b", "windows": ["/"], "depends_on": ["a"] }
+  ]
+}"#;
+        assert_eq!(
+            check(&[("cycle.json", text)]),
+            ["cycle.json:2:3: Session dependencies: dependency cycle a -> b -> a"]
+        );
+    }
+
+    #[test]
+    fn toml_and_yaml_parse_errors() {
+        assert_eq!(
+            check(&[("broken.toml", "[markers]\nexact = [\".git\"\n")]),
+            ["broken.toml:3:1: invalid array, expected `]`"]
+        );
+        assert_eq!(
+            check(&[("broken.yaml", "sessions:\n  - name: a\n   windows: []\n")]),
+            ["broken.yaml:3:4: did not find expected '-' indicator"]
+        );
+    }
+
+    #[test]
+    fn imported_files_are_checked() {
+        let text = r#"{ "imports": ["imported.json", "missing.json"] }"#;
+        assert_eq!(
+            check(&[("imports.json", text), ("imported.json", r#"{ "nope": 1 }"#)]),
+            [
+                "imports.json:1:32: imported file missing.json does not exist",
+                "imported.json:1:3: unknown key `nope`",
+            ]
+        );
+    }
+}
@@ -1,256 +1,318 @@
-use log::trace;
-use std::process;
+mod check_config;
+mod completions;
+mod config;
+mod doctor;
+mod fzf;
+mod kill_session;
+mod list;
+mod man;
+mod new_project;
+mod new_session;
+mod new_window;
+mod print_config;
+mod prune;
+mod selectors;
+mod sessions;
+mod start;
+mod windows;
 
-use crate::config::{read_config, Session};
-use crate::fs::{expand, trim_session_name, trim_window_name};
-use crate::selectors::{pick_project, select_from_list};
-use crate::tmux::{execute_tmux_command, execute_tmux_command_with_stdin, execute_tmux_window_command};
+use std::path::Path;
 
-use clap::{Arg, ArgAction};
+use log::{info, trace, LevelFilter};
+use pfp::config::{read_config, Config, SYSTEM_CONFIG_PATH};
+use pfp::deps::{ensure_installed, Dependency};
+use pfp::discovery::discover_projects;
+use pfp::dry_run::{set_dry_run, take_recorded};
+use pfp::fs::expand;
+use pfp::Error;
+use selectors::set_picker;
 
-static APP_NAME: &str = "pfp";
-static CONFIG_PATH_DEFAULT: &str = "${XDG_CONFIG_HOME}/pfp/config.json";
-
-const KILL_SESSION_SUBC: &str = "kill-session";
-const SESSIONS_SUBC: &str = "sessions";
-const START_SUBC: &str = "start";
-const PRINT_CONFIG_SUBC: &str = "print-config";
-const NEW_SESSION_SUBC: &str = "new-session";
-const NEW_WINDOW_SUBC: &str = "new-window";
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand};
+use clap_complete::engine::CompletionCandidate;
 
-const CONFIG_ARG: &str = "config";
-const START_INHERIT_STDIN_ARG: &str = "attach"; // inherit stdin
-
-pub(crate) fn cli() -> Result<(), super::Error> {
-    // parse cli args
-    let mut cmd = clap::Command::new(APP_NAME)
-        .about("Pfp helps you manage your projects with tmux sessions and windows")
-        .arg(
-            Arg::new(CONFIG_ARG)
-                .short('c')
-                .long(CONFIG_ARG)
-                .action(ArgAction::Set)
-                .default_value(CONFIG_PATH_DEFAULT)
-                .value_name("FILE")
-                .help("config file full path"),
-        )
-        .subcommand(clap::Command::new(PRINT_CONFIG_SUBC).about("Print parsed config to stdout"))
-        .subcommand(clap::Command::new(NEW_SESSION_SUBC).about("Pick a path and create new tmux session"))
-        .subcommand(clap::Command::new(NEW_WINDOW_SUBC).about("Pick a path and create new tmux window"))
-        .subcommand(
-            clap::Command::new(KILL_SESSION_SUBC)
-                .about("Kill current session and switch to last/previous session"),
-        )
-        .subcommand(
-            clap::Command::new(SESSIONS_SUBC)
-                .about("Show list of active sessions, select one to switch to it"),
-        )
-        .subcommand(
-            clap::Command::new(START_SUBC)
-                .about("Start tmux sessions from predefined list")
-                .arg(
-                    Arg::new(START_INHERIT_STDIN_ARG)
-                        .short('a')
-                        .long(START_INHERIT_STDIN_ARG)
-                        .action(ArgAction::SetTrue)
-                        .help("attach to tmux session after start"),
-                ),
-        );
+static APP_NAME: &str = "pfp";
+/// default user config path without extension, first existing of [`CONFIG_EXTENSIONS`] is used
+static CONFIG_PATH_DEFAULT: &str = "${XDG_CONFIG_HOME}/pfp/config";
+/// extensions of the default user config, in the order they are looked up (json is used if none exists)
+static CONFIG_EXTENSIONS: [&str; 5] = ["json", "jsonc", "toml", "yaml", "yml"];
+/// env var with log filters
+static LOG_ENV: &str = "PFP_LOG";
 
-    let help = cmd.render_help();
-    let arg_matches = cmd.get_matches();
+/// Pfp helps you manage your projects with tmux sessions and windows
+#[derive(Parser)]
+#[command(name = APP_NAME)]
+pub struct Cli {
+    #[command(flatten)]
+    global: GlobalArgs,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-    let path = expand(
-        arg_matches
-            .get_one::<String>(CONFIG_ARG)
-            .ok_or_else(|| super::Error::CmdArg(format!("error: wrong type used for {}", CONFIG_ARG)))?,
-    )?;
+/// flags accepted by every subcommand
+#[derive(Args)]
+struct GlobalArgs {
+    /// config file full path [default: ${XDG_CONFIG_HOME}/pfp/config.{json,jsonc,toml,yaml,yml}]
+    #[arg(short, long, global = true, value_name = "FILE")]
+    config: Option<String>,
+    /// fzf-compatible picker command used for all pickers (e.g. sk, or "fzf-tmux -p")
+    #[arg(long, global = true, value_name = "COMMAND", default_value = "fzf")]
+    picker: String,
+    /// print tmux commands (and hooks) that would change tmux state instead of running them
+    #[arg(long, global = true)]
+    dry_run: bool,
+    /// log more: -v for info and timings, -vv for debug, -vvv for trace (PFP_LOG env var overrides it, e.g. PFP_LOG=pfp=debug)
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// exit successfully without printing anything if pick is cancelled (for keybindings), other errors are still reported
+    #[arg(long, global = true)]
+    quiet: bool,
+    /// write log to the file instead of stderr
+    #[arg(long, global = true, value_name = "FILE")]
+    log_file: Option<String>,
+}
 
-    let config = {
-        let cfg = read_config(&path);
-        if cfg.is_err() && path == CONFIG_PATH_DEFAULT {
-            // default value is used for --config and config does not exist in file system
-            // -> use default config value
-            cfg.map_err(|e| println!("{}, config path={}, using default config", e, path))
-                .unwrap_or_default()
-        } else {
-            // either read_config succeeded, or it failed with provided custom --config path
-            // -> continue or propagate error
-            cfg?
+impl GlobalArgs {
+    /// flags to pass to pfp run from pfp itself (e.g. by picker key bindings)
+    fn to_args(&self) -> Vec<String> {
+        let mut args = vec!["--picker".to_owned(), self.picker.to_owned()];
+        if let Some(config) = &self.config {
+            args.extend(["--config".to_owned(), config.to_owned()]);
         }
-    };
-    trace!("config {:#?}", config);
-
-    match arg_matches.subcommand() {
-        Some((KILL_SESSION_SUBC, _)) => {
-            let mut session_name =
-                String::from_utf8(execute_tmux_command("tmux display-message -p '#S'")?.stdout)?;
-            session_name.retain(|x| x != '\'' && x != '\n');
-            let out = execute_tmux_command("tmux switch-client -l")?;
-            if !out.status.success() {
-                execute_tmux_command("tmux switch-client -p")?;
-            }
-            execute_tmux_command(&format!("tmux kill-session -t {}", session_name,))?;
+        if self.dry_run {
+            args.push("--dry-run".to_owned());
         }
-        Some((PRINT_CONFIG_SUBC, _)) => {
-            println!("{:#?}", config)
+        if self.verbose > 0 {
+            args.push(format!("-{}", "v".repeat(self.verbose.into())));
         }
-        Some((SESSIONS_SUBC, _)) => {
-            let mut current_session =
-                String::from_utf8(execute_tmux_command("tmux display-message -p '#S:#I'")?.stdout)?;
-            current_session.retain(|x| x != '\'' && x != '\n');
-            let mut sessions = String::from_utf8(
-                execute_tmux_command("tmux list-sessions -F '#S:#I,#{session_id}'")?.stdout,
-            )?
-            .trim_end()
-            .to_owned();
-            sessions.retain(|x| x != '\'');
-            let mut s = sessions
-                .split('\n')
-                .map(|x| x.split_once(',').expect("Wrong list-sessions format!"))
-                .collect::<Vec<(&str, &str)>>();
-            s.sort_by_key(|k| k.1);
-            sessions = s.into_iter().map(|x| x.0).collect::<Vec<&str>>().join("\n");
-            let idx = sessions
-                .split('\n')
-                .enumerate()
-                .find(|x| x.1 == current_session)
-                .map(|x| x.0)
-                .unwrap_or(0);
-            let mut pick = select_from_list(
-                &sessions,
-                "Active sessions:",
-                &[
-                    "--layout",
-                    "reverse",
-                    "--preview",
-                    "tmux capture-pane -ept {}",
-                    "--preview-window",
-                    "right:nohidden",
-                    "--sync",
-                    "--bind",
-                    &format!("load:pos({})", idx + 1),
-                ],
-            )?;
-            pick.retain(|x| x != '\'' && x != '\n');
-            if !pick.is_empty() {
-                execute_tmux_command(&format!("tmux switch-client -t {}", pick))?;
-            }
+        if let Some(log_file) = &self.log_file {
+            args.extend(["--log-file".to_owned(), log_file.to_owned()]);
         }
-        Some((START_SUBC, arg_matches)) => {
-            let stdin_opt = match arg_matches.get_one(START_INHERIT_STDIN_ARG).unwrap_or(&false) {
-                true => process::Stdio::inherit(),
-                false => process::Stdio::piped(),
-            };
-            if config.sessions.is_empty() {
-                execute_tmux_command_with_stdin("tmux", stdin_opt)?;
-                return Ok(());
-            }
-            let mut sessions = String::from_utf8(execute_tmux_command("tmux list-sessions -F '#S'")?.stdout)?;
-            sessions.retain(|x| x != '\'');
-            let pick = select_from_list(
-                &config
-                    .sessions
-                    .iter()
-                    .map(|s| s.name)
-                    .collect::<Vec<&str>>()
-                    .join("\n"),
-                "Start sessions:",
-                &[
-                    "-m",
-                    "--layout",
-                    "reverse",
-                    "--preview",
-                    &format!(
-                        "echo '{}'",
-                        config
-                            .sessions
-                            .iter()
-                            .map(Session::to_string)
-                            .collect::<Vec<_>>()
-                            .join("\n")
-                    ),
-                    "--preview-window",
-                    "right:nohidden",
-                ],
-            )?;
-            let picked_sessions = pick.split('\n').filter(|x| !x.is_empty()).collect::<Vec<&str>>();
-            for session in config.sessions {
-                if picked_sessions.contains(&session.name) {
-                    let session_exists = sessions
-                        .split('\n')
-                        .find(|x| *x == session.name)
-                        .map(|_| true)
-                        .unwrap_or(false);
-                    if session_exists {
-                        println!("session {} exists", session.name);
-                        continue;
-                    }
-                    let iter = session.windows.iter();
-                    for (i, window) in iter.enumerate() {
-                        let window = &expand(window.trim_end_matches('/'))?;
-                        let cmd = &match i {
-                            // create session with first window
-                            0 => format!(
-                                "tmux new-session -d -s {} -n {} -c {}",
-                                session.name,
-                                trim_window_name(window)?,
-                                window,
-                            ),
-                            // create window in current session
-                            _ => format!(
-                                "tmux new-window -d -n {} -P -F '#S:#I' -c {}",
-                                trim_window_name(window)?,
-                                window,
-                            ),
-                        };
-                        let mut window = String::from_utf8(execute_tmux_window_command(cmd, window)?.stdout)?;
+        args
+    }
+}
 
-                        // move consequent windows to new session
-                        if i > 0 {
-                            window.retain(|x| x != '\'' && x != '\n');
-                            execute_tmux_command(&format!(
-                                "tmux move-window -s {} -t {}:",
-                                window, session.name
-                            ))?;
-                        }
-                    }
-                    // renumber windows with no-op move
-                    execute_tmux_command(&format!(
-                        "tmux movew -r -s {}:1 -t {}:1",
-                        session.name, session.name
-                    ))?;
-                }
-            }
-            execute_tmux_command_with_stdin("tmux attach", stdin_opt)?;
-        }
-        Some((NEW_WINDOW_SUBC, _)) => {
-            let pick = pick_project(&config, "New window:")?;
-            execute_tmux_window_command(
-                &format!("tmux new-window -n {} -c {}", &trim_window_name(&pick)?, &pick),
-                &pick,
-            )?;
-        }
-        Some((NEW_SESSION_SUBC, _)) => {
-            let pick = pick_project(&config, "New session:")?;
-            // spawn tmux session
-            let mut window_name = trim_window_name(&pick)?;
-            let session_name = trim_session_name(&window_name);
-            execute_tmux_window_command(
-                &format!(
-                    "tmux new-session -d -s {} -n {} -c {}",
-                    session_name, window_name, &pick
-                ),
-                &pick,
-            )?;
-            window_name.retain(|x| x != '\'' && x != '\n');
-            execute_tmux_command(&format!("tmux switch-client -t {}:1", session_name))?;
+#[derive(Subcommand)]
+enum Command {
+    PrintConfig(print_config::PrintConfigArgs),
+    CheckConfig(check_config::CheckConfigArgs),
+    #[command(subcommand)]
+    Config(config::ConfigCommand),
+    List(list::ListArgs),
+    NewSession(new_session::NewSessionArgs),
+    Open(new_session::OpenArgs),
+    NewWindow(new_window::NewWindowArgs),
+    NewProject(new_project::NewProjectArgs),
+    KillSession(kill_session::KillSessionArgs),
+    Prune(prune::PruneArgs),
+    Sessions(sessions::SessionsArgs),
+    Windows(windows::WindowsArgs),
+    Panes(windows::PanesArgs),
+    Start(start::StartArgs),
+    Doctor(doctor::DoctorArgs),
+    Completions(completions::CompletionsArgs),
+    Man(man::ManArgs),
+}
+
+impl Command {
+    /// external binaries the subcommand can't work without, checked before it is run
+    fn dependencies(&self) -> &'static [Dependency] {
+        match self {
+            Command::NewSession(_)
+            | Command::Open(_)
+            | Command::NewWindow(_)
+            | Command::NewProject(_)
+            | Command::Sessions(_)
+            | Command::Windows(_)
+            | Command::Panes(_) => &[Dependency::Tmux, Dependency::Picker],
+            Command::KillSession(_) | Command::Prune(_) | Command::Start(_) => &[Dependency::Tmux],
+            _ => &[],
         }
+    }
+}
+
+pub fn cli() -> Result<(), Error> {
+    // parse cli args
+    let cli = Cli::parse();
+    init_logger(&cli.global)?;
+    let quiet = cli.global.quiet;
+    let result = run(cli);
+    // commands recorded in dry run mode are printed even if a later step failed
+    for command in take_recorded() {
+        println!("{}", command);
+    }
+    match result {
+        Err(Error::EmptyPick()) if quiet => Ok(()),
+        result => result,
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Error> {
+    set_picker(&cli.global.picker);
+    set_dry_run(cli.global.dry_run);
+
+    // these subcommands work with config files themselves (or don't need config), so they are run before config is parsed
+    let command = match cli.command {
+        Some(Command::Completions(args)) => return completions::run(args),
+        Some(Command::Man(args)) => return man::run(args),
+        Some(Command::Doctor(args)) => return doctor::run(&cli.global, args),
+        Some(Command::CheckConfig(args)) => return check_config::run(&cli.global, args),
+        Some(Command::Config(command)) => return config::run(&cli.global, command),
+        Some(command) => command,
         // no subcommand
-        _ => {
-            println!("{}", help);
+        None => {
+            println!("{}", Cli::command().render_help());
+            return Ok(());
+        }
+    };
+
+    ensure_installed(command.dependencies(), &cli.global.picker)?;
+
+    let layers = config_layers(&cli.global)?;
+
+    let config = read_config(&layers)?;
+    trace!("config {:#?}", config);
+
+    match command {
+        Command::PrintConfig(args) => print_config::run(&config, args),
+        Command::List(args) => list::run(&config, args),
+        Command::NewSession(args) => new_session::run(&config, args),
+        Command::Open(args) => new_session::run_open(&config, args),
+        Command::NewWindow(args) => new_window::run(&config, args),
+        Command::NewProject(args) => new_project::run(&config, args),
+        Command::KillSession(args) => kill_session::run(&config, args),
+        Command::Prune(args) => prune::run(&config, args),
+        Command::Sessions(args) => sessions::run(&config, &cli.global, args),
+        Command::Windows(args) => windows::run_windows(&config, args),
+        Command::Panes(args) => windows::run_panes(&config, args),
+        Command::Start(args) => start::run(&config, args),
+        Command::Completions(_)
+        | Command::Man(_)
+        | Command::Doctor(_)
+        | Command::CheckConfig(_)
+        | Command::Config(_) => {
+            unreachable!("handled before config is read")
         }
     }
+}
 
+/// logs warnings and errors by default, more with -v flags, PFP_LOG env var (env_logger syntax) takes precedence
+fn init_logger(global: &GlobalArgs) -> Result<(), Error> {
+    let level = match global.verbose {
+        0 => LevelFilter::Warn,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    let mut builder = env_logger::Builder::new();
+    builder
+        .filter_level(level)
+        .parse_env(env_logger::Env::new().filter(LOG_ENV));
+    if let Some(path) = &global.log_file {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(expand(path)?)?;
+        builder.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+    builder.init();
     Ok(())
 }
+
+/// paths of config files to merge: system config (if exists) and user config
+fn config_layers(global: &GlobalArgs) -> Result<Vec<String>, Error> {
+    let layers = find_config_layers(global.config.as_deref())?;
+    // default value is used for --config and config does not exist in file system
+    // -> system config (if any) or default config value is used
+    if layers.is_empty() {
+        info!(
+            "config path={}.{{{}}} does not exist, using default config",
+            CONFIG_PATH_DEFAULT,
+            CONFIG_EXTENSIONS.join(",")
+        )
+    }
+    Ok(layers)
+}
+
+/// user config path, either provided with --config or the default one
+fn config_path(global: &GlobalArgs) -> Result<String, Error> {
+    match global.config.as_deref() {
+        Some(path) => expand(path),
+        None => default_config_path(),
+    }
+}
+
+/// first existing default user config (see [`CONFIG_EXTENSIONS`]), or config.json if none exists
+fn default_config_path() -> Result<String, Error> {
+    let base = expand(CONFIG_PATH_DEFAULT)?;
+    let paths = CONFIG_EXTENSIONS.map(|ext| format!("{}.{}", base, ext));
+    Ok(paths
+        .iter()
+        .find(|path| Path::new(path).is_file())
+        .unwrap_or(&paths[0])
+        .to_owned())
+}
+
+fn find_config_layers(config_arg: Option<&str>) -> Result<Vec<String>, Error> {
+    let mut layers = vec![];
+    if Path::new(SYSTEM_CONFIG_PATH).is_file() {
+        layers.push(SYSTEM_CONFIG_PATH.to_owned());
+    }
+    match config_arg {
+        // user config provided with --config
+        Some(config_arg) => layers.push(expand(config_arg)?),
+        // user config exists at the default path
+        None => match default_config_path() {
+            Ok(path) if Path::new(&path).is_file() => layers.push(path),
+            _ => {}
+        },
+    }
+    Ok(layers)
+}
+
+/// config for dynamic shell completions, read from the default config path (completions can't see --config value)
+fn completion_config() -> Option<Config> {
+    let layers = find_config_layers(None).ok()?;
+    read_config(&layers).ok()
+}
+
+/// names of predefined sessions, with their windows as description, and session groups, with their sessions
+fn session_candidates() -> Vec<CompletionCandidate> {
+    let Some(config) = completion_config() else {
+        return vec![];
+    };
+    let mut candidates = config
+        .sessions
+        .iter()
+        .map(|s| CompletionCandidate::new(&s.name).help(Some(s.windows.join(", ").into())))
+        .collect::<Vec<_>>();
+    let mut groups = config
+        .sessions
+        .iter()
+        .flat_map(|s| s.groups.iter())
+        .collect::<Vec<_>>();
+    groups.sort();
+    groups.dedup();
+    for group in groups {
+        let sessions = config
+            .sessions
+            .iter()
+            .filter(|s| s.groups.contains(group))
+            .map(|s| s.name.as_str())
+            .collect::<Vec<&str>>();
+        candidates.push(CompletionCandidate::new(group).help(Some(sessions.join(", ").into())));
+    }
+    candidates
+}
+
+/// paths of discovered projects
+fn project_candidates() -> Vec<CompletionCandidate> {
+    completion_config()
+        .and_then(|config| discover_projects(&config).ok())
+        .map(|projects| {
+            projects
+                .into_iter()
+                .map(|p| CompletionCandidate::new(p.path))
+                .collect()
+        })
+        .unwrap_or_default()
+}
@@ -0,0 +1,25 @@
+use clap::Args;
+use pfp::check::check_config;
+use pfp::config::ConfigError;
+use pfp::Error;
+
+use super::{config_layers, GlobalArgs};
+
+/// Validate config files and report issues found in them
+#[derive(Args)]
+pub(super) struct CheckConfigArgs {}
+
+pub(super) fn run(global: &GlobalArgs, _args: CheckConfigArgs) -> Result<(), Error> {
+    let layers = config_layers(global)?;
+    let issues = check_config(&layers);
+    for issue in issues.iter() {
+        println!("{}", issue);
+    }
+    match issues.len() {
+        0 => {
+            println!("config is valid: {}", layers.join(", "));
+            Ok(())
+        }
+        n => Err(ConfigError::Invalid(n).into()),
+    }
+}
@@ -0,0 +1,36 @@
+use clap::{Args, ValueEnum};
+use clap_complete::env::Shells;
+use pfp::Error;
+
+use super::APP_NAME;
+
+/// env var the shell completion scripts call pfp with
+pub(super) const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Print shell completion script, e.g. `source <(pfp completions bash)`
+#[derive(Args)]
+pub(super) struct CompletionsArgs {
+    /// shell to print completion script for
+    shell: Shell,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+pub(super) fn run(args: CompletionsArgs) -> Result<(), Error> {
+    let name = match args.shell {
+        Shell::Bash => "bash",
+        Shell::Zsh => "zsh",
+        Shell::Fish => "fish",
+    };
+    let shells = Shells::builtins();
+    let shell = shells
+        .completer(name)
+        .ok_or_else(|| Error::CmdArg(format!("error: unsupported shell {}", name)))?;
+    shell.write_registration(COMPLETE_VAR, APP_NAME, APP_NAME, APP_NAME, &mut std::io::stdout())?;
+    Ok(())
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use clap::{Args, Subcommand};
+use pfp::config::{self, migrate_config, ConfigError, Format};
+use pfp::Error;
+
+use super::{config_path, GlobalArgs};
+
+/// Manage config files
+#[derive(Subcommand)]
+pub(super) enum ConfigCommand {
+    Migrate(MigrateArgs),
+    Schema(SchemaArgs),
+}
+
+/// Rewrite config file from legacy format into the current one, keeping comments
+#[derive(Args)]
+pub(super) struct MigrateArgs {
+    /// config file to migrate, --config file by default
+    #[arg(value_name = "FILE")]
+    file: Option<String>,
+    /// print migrated config instead of rewriting the file
+    #[arg(long)]
+    stdout: bool,
+}
+
+/// Print JSON schema of the config file
+#[derive(Args)]
+pub(super) struct SchemaArgs {}
+
+pub(super) fn run(global: &GlobalArgs, command: ConfigCommand) -> Result<(), Error> {
+    match command {
+        ConfigCommand::Schema(_) => println!("{}", config::schema()?),
+        ConfigCommand::Migrate(args) => {
+            let path = match args.file {
+                Some(path) => path,
+                None => config_path(global)?,
+            };
+            if Format::from_path(Path::new(&path)) != Format::Json {
+                return Err(Error::CmdArg(format!(
+                    "{}: only JSONC configs can be in legacy format",
+                    path
+                )));
+            }
+            let text = std::fs::read_to_string(&path).map_err(ConfigError::from)?;
+            match migrate_config(&text)? {
+                None => println!("{} is up to date", path),
+                Some(migrated) if args.stdout => print!("{}", migrated),
+                Some(migrated) => {
+                    std::fs::write(&path, migrated)?;
+                    println!("migrated {}", path);
+                }
+            }
+        }
+    }
+    Ok(())
+}
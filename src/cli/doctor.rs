@@ -0,0 +1,39 @@
+use clap::Args;
+use pfp::deps::check_dependencies;
+use pfp::Error;
+
+use super::GlobalArgs;
+
+/// Check that tmux, picker, tree and $EDITOR are installed, print their versions and how to fix problems
+#[derive(Args)]
+pub(super) struct DoctorArgs {}
+
+pub(super) fn run(global: &GlobalArgs, _args: DoctorArgs) -> Result<(), Error> {
+    let statuses = check_dependencies(&global.picker);
+    for status in statuses.iter() {
+        let state = match (&status.path, &status.problem) {
+            (_, None) => "ok",
+            (None, Some(_)) if status.dependency.is_required() => "missing",
+            _ => "warning",
+        };
+        let name = match status.name.as_str() {
+            "" => "$EDITOR",
+            name => name,
+        };
+        match (&status.path, &status.version) {
+            (Some(path), Some(version)) => println!("{:<8}{}: {} ({})", state, name, version, path.display()),
+            (Some(path), None) => println!("{:<8}{}: {}", state, name, path.display()),
+            (None, _) => println!("{:<8}{}", state, name),
+        }
+        if let Some(problem) = &status.problem {
+            println!("{:<8}{}", "", problem);
+        }
+    }
+    match statuses
+        .iter()
+        .find(|status| status.dependency.is_required() && status.path.is_none())
+    {
+        Some(status) => Err(Error::MissingBinary(status.name.to_owned())),
+        None => Ok(()),
+    }
+}
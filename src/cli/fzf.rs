@@ -0,0 +1,56 @@
+use std::{
+    io::{Read, Write},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use pfp::{measure, spawn_error, Error};
+
+/// picker command (fzf by default), split on whitespace into program and its args
+pub(super) static PICKER: OnceLock<Vec<String>> = OnceLock::new();
+
+pub(super) fn execute_fzf_command<'a>(
+    args: impl Iterator<Item = &'a str>,
+    input: &str,
+) -> Result<String, Error> {
+    measure("fzf", || run_picker(args, input))
+}
+
+fn run_picker<'a>(args: impl Iterator<Item = &'a str>, input: &str) -> Result<String, Error> {
+    let picker = PICKER.get_or_init(|| vec!["fzf".to_owned()]);
+    let mut child = Command::new(&picker[0])
+        .args(&picker[1..])
+        .stdout(Stdio::piped())
+        .stdin(Stdio::piped())
+        .args(args)
+        .spawn()
+        .map_err(|err| spawn_error(&picker[0], err))?;
+    let mut result = String::new();
+    {
+        // stdin is closed after writing the input, so that fzf knows the list is complete (needed for --filter)
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| Error::UnwrapIOStream("Could not get cmd.stdin"))?;
+        stdin.write_all(input.as_bytes())?;
+        stdin.flush()?;
+    }
+    {
+        let stdout = child
+            .stdout
+            .as_mut()
+            .ok_or_else(|| Error::UnwrapIOStream("Could not get cmd.stdout"))?;
+        stdout.read_to_string(&mut result)?;
+    }
+    // 130 is a cancelled pick, 1 with no output is no match (both result in empty pick),
+    // anything else is a failure (e.g. unknown option), it shouldn't look like a cancel
+    match child.wait()?.code() {
+        Some(0 | 130) => Ok(result),
+        Some(1) if result.trim().is_empty() => Ok(result),
+        code => Err(Error::Picker(format!(
+            "{} exited with {}",
+            picker.join(" "),
+            code.map_or("signal".to_owned(), |c| format!("code {}", c))
+        ))),
+    }
+}
@@ -0,0 +1,83 @@
+use std::io::IsTerminal;
+
+use super::selectors::{confirm, select_from_list, TARGET_PICKER_ARGS};
+use clap::Args;
+use pfp::config::Config;
+use pfp::session::{busy_commands, kill_sessions, list_sessions, SessionInfo};
+use pfp::tmux::tmux_output;
+use pfp::Error;
+
+use super::sessions::sessions_list;
+
+/// Kill current session (or sessions picked with --multi) and switch to last/previous session
+#[derive(Args)]
+pub(super) struct KillSessionArgs {
+    /// pick sessions to kill from the list of active sessions
+    #[arg(short, long)]
+    multi: bool,
+    /// don't ask for confirmation when panes run something other than a shell
+    #[arg(short, long)]
+    force: bool,
+}
+
+pub(super) fn run(config: &Config, args: KillSessionArgs) -> Result<(), Error> {
+    let sessions = list_sessions()?;
+    let ids = match args.multi {
+        false => vec![tmux_output(&["display-message", "-p", "#{session_id}"])?],
+        true => select_from_list(
+            &sessions_list(&sessions),
+            "Kill sessions:",
+            &[&["-m"][..], &TARGET_PICKER_ARGS].concat(),
+        )?
+        .lines()
+        .filter_map(|line| line.split('\t').next())
+        .filter(|id| !id.is_empty())
+        .map(str::to_owned)
+        .collect(),
+    };
+
+    confirm_kill(config, &sessions, &ids, args.force)
+}
+
+/// kills sessions (given by ids), sessions running something other than a shell are killed only if confirmed
+/// (or if `force` is set), client is switched to another session if its session is killed
+pub(super) fn confirm_kill(
+    config: &Config,
+    sessions: &[SessionInfo],
+    ids: &[String],
+    force: bool,
+) -> Result<(), Error> {
+    // the pane pfp is run in (where the picker ran in --multi mode) is not taken into account, it always runs pfp
+    let caller_pane = std::env::var("TMUX_PANE").ok();
+    let mut killed = vec![];
+    for id in ids.iter() {
+        let name = sessions
+            .iter()
+            .find(|s| &s.id == id)
+            .map(|s| s.name.as_str())
+            .unwrap_or(id);
+        let busy = busy_commands(id, caller_pane.as_deref())?;
+        if !force && !busy.is_empty() {
+            if !std::io::stdin().is_terminal() {
+                return Err(Error::CmdArg(format!(
+                    "session {} runs {}, use --force to kill it without confirmation",
+                    name,
+                    busy.join(", ")
+                )));
+            }
+            if !confirm(&format!(
+                "Session {} runs {}, kill it? [y/N]: ",
+                name,
+                busy.join(", ")
+            ))? {
+                continue;
+            }
+        }
+        killed.push(id.as_str());
+    }
+    if killed.is_empty() {
+        return Ok(());
+    }
+
+    kill_sessions(config, &killed)
+}
@@ -0,0 +1,43 @@
+use std::io::Write;
+
+use clap::{Args, ValueEnum};
+use pfp::config::Config;
+use pfp::discovery::discover_projects;
+use pfp::Error;
+
+/// Print projects the picker would show, without picking
+#[derive(Args)]
+pub(super) struct ListArgs {
+    /// output format: path per line, NUL-separated paths, or JSON with match details
+    #[arg(short, long, value_enum, default_value_t = ListFormat::Plain)]
+    format: ListFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum ListFormat {
+    Plain,
+    Nul,
+    Json,
+}
+
+pub(super) fn run(config: &Config, args: ListArgs) -> Result<(), Error> {
+    let projects = discover_projects(config)?;
+    match args.format {
+        ListFormat::Json => println!(
+            "{}",
+            serde_jsonc::to_string_pretty(&projects).map_err(std::io::Error::from)?
+        ),
+        ListFormat::Nul => {
+            let mut stdout = std::io::stdout().lock();
+            for project in projects.iter() {
+                write!(stdout, "{}\0", project.path)?;
+            }
+        }
+        ListFormat::Plain => {
+            for project in projects.iter() {
+                println!("{}", project.path);
+            }
+        }
+    }
+    Ok(())
+}
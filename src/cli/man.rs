@@ -0,0 +1,13 @@
+use clap::{Args, CommandFactory};
+use pfp::Error;
+
+use super::Cli;
+
+/// Print man page (roff) to stdout
+#[derive(Args)]
+pub(super) struct ManArgs {}
+
+pub(super) fn run(_args: ManArgs) -> Result<(), Error> {
+    clap_mangen::Man::new(Cli::command()).render(&mut std::io::stdout())?;
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use super::selectors::{read_input, select_from_list};
+use clap::Args;
+use log::error;
+use pfp::config::{Config, Template};
+use pfp::dry_run::{is_dry_run, record};
+use pfp::fs::{copy_dir, expand};
+use pfp::hooks::run_hooks;
+use pfp::Error;
+
+use super::new_session::new_session;
+
+/// Create new project dir from template and open it in new tmux session
+#[derive(Args)]
+pub(super) struct NewProjectArgs {}
+
+pub(super) fn run(config: &Config, _args: NewProjectArgs) -> Result<(), Error> {
+    let path = new_project(config)?;
+    new_session(config, &path)
+}
+
+/// asks for project name, template and include root,
+/// creates project dir from template and runs template's init commands in it
+/// returns path of the created project
+fn new_project(config: &Config) -> Result<String, Error> {
+    let name = read_input("Project name: ")?;
+    if name.contains('/') || name == "." || name == ".." {
+        return Err(Error::Project(format!("invalid project name {}", name)));
+    }
+
+    // pick template (if any are configured)
+    let template = match config.templates.len() {
+        0 => None,
+        _ => {
+            let mut pick = select_from_list(
+                &config
+                    .templates
+                    .iter()
+                    .map(|t| t.name.as_str())
+                    .collect::<Vec<&str>>()
+                    .join("\n"),
+                "Template:",
+                &["--layout", "reverse"],
+            )?;
+            pick.retain(|x| x != '\n');
+            config.templates.iter().find(|t| t.name == pick)
+        }
+    };
+
+    // pick root to create project in
+    let roots = config
+        .include
+        .iter()
+        .flat_map(|entry| entry.paths.iter())
+        .map(|path| expand(path))
+        .collect::<Result<Vec<String>, _>>()?;
+    let root = match roots.as_slice() {
+        [root] => root.to_owned(),
+        _ => {
+            let mut pick = select_from_list(&roots.join("\n"), "Create in:", &["--layout", "reverse"])?;
+            pick.retain(|x| x != '\n');
+            pick
+        }
+    };
+
+    let path = format!("{}/{}", root.trim_end_matches('/'), name);
+    if Path::new(&path).exists() {
+        return Err(Error::Project(format!("{} already exists", path)));
+    }
+    if is_dry_run() {
+        record(&["mkdir", "-p", &path]);
+    } else {
+        std::fs::create_dir_all(&path)?;
+    }
+    // don't leave half-created project behind, so that creating it again doesn't fail with "already exists"
+    if let Err(err) = init_project(template, &name, &path) {
+        if !is_dry_run() {
+            if let Err(remove_err) = std::fs::remove_dir_all(&path) {
+                error!("error removing {}: {}", path, remove_err);
+            }
+        }
+        return Err(err);
+    }
+    Ok(path)
+}
+
+/// copies template contents into the project dir and runs template's init commands in it
+fn init_project(template: Option<&Template>, name: &str, path: &str) -> Result<(), Error> {
+    let Some(template) = template else {
+        return Ok(());
+    };
+    if let Some(template_path) = &template.path {
+        let template_path = expand(template_path)?;
+        if is_dry_run() {
+            record(&["cp", "-R", &format!("{}/.", template_path), path]);
+        } else {
+            copy_dir(Path::new(&template_path), Path::new(path))?;
+        }
+    }
+    run_hooks(
+        &template.init,
+        path,
+        &[("PFP_PROJECT_NAME", name), ("PFP_PROJECT_PATH", path)],
+    )
+}
@@ -0,0 +1,70 @@
+use super::selectors::{confirm, pick_project, resolve_project};
+use clap::Args;
+use clap_complete::engine::ArgValueCandidates;
+use pfp::config::Config;
+use pfp::project::{is_trusted, project_config_path, trust};
+use pfp::session::{create_session, switch_client};
+use pfp::Error;
+
+use super::project_candidates;
+
+/// Pick a path and create new tmux session
+#[derive(Args)]
+pub(super) struct NewSessionArgs {
+    /// project path or fuzzy query, picker is shown only if query is ambiguous
+    #[arg(short, long, value_name = "QUERY")]
+    query: Option<String>,
+    #[command(flatten)]
+    resolve: ResolveArgs,
+}
+
+/// Create new tmux session for the project given by path or fuzzy query
+#[derive(Args)]
+pub(super) struct OpenArgs {
+    /// project path or fuzzy query, picker is shown only if query is ambiguous
+    #[arg(value_name = "PATH_OR_QUERY", add = ArgValueCandidates::new(project_candidates))]
+    query: String,
+    #[command(flatten)]
+    resolve: ResolveArgs,
+}
+
+/// Options of resolving fuzzy query to a project, shared by commands taking a query
+#[derive(Args)]
+pub(super) struct ResolveArgs {
+    /// use the best match instead of showing the picker if query is ambiguous
+    #[arg(long)]
+    first: bool,
+}
+
+pub(super) fn run(config: &Config, args: NewSessionArgs) -> Result<(), Error> {
+    let pick = match args.query {
+        Some(query) => resolve_project(config, &query, "New session:", args.resolve.first)?,
+        None => pick_project(config, "New session:")?,
+    };
+    new_session(config, &pick)
+}
+
+pub(super) fn run_open(config: &Config, args: OpenArgs) -> Result<(), Error> {
+    let pick = resolve_project(config, &args.query, "New session:", args.resolve.first)?;
+    new_session(config, &pick)
+}
+
+/// spawns tmux session for the picked path and switches to it
+pub(super) fn new_session(config: &Config, pick: &str) -> Result<(), Error> {
+    let session_name = create_session(config, pick, ensure_trusted)?;
+    switch_client(&session_name)
+}
+
+/// checks whether user trusts commands from the project config file, asks for confirmation if not decided yet
+fn ensure_trusted(dir: &str, contents: &str) -> Result<bool, Error> {
+    if is_trusted(dir, contents)? {
+        return Ok(true);
+    }
+    let path = project_config_path(dir);
+    println!("{}:\n{}", path, contents);
+    if !confirm(&format!("Run commands from {}? [y/N]: ", path))? {
+        return Ok(false);
+    }
+    trust(dir, contents)?;
+    Ok(true)
+}
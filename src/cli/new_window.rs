@@ -0,0 +1,15 @@
+use super::selectors::pick_project;
+use clap::Args;
+use pfp::config::Config;
+use pfp::session::create_window;
+use pfp::Error;
+
+/// Pick a path and create new tmux window
+#[derive(Args)]
+pub(super) struct NewWindowArgs {}
+
+pub(super) fn run(config: &Config, _args: NewWindowArgs) -> Result<(), Error> {
+    let pick = pick_project(config, "New window:")?;
+    create_window(config, &pick)?;
+    Ok(())
+}
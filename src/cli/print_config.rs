@@ -0,0 +1,28 @@
+use clap::{Args, ValueEnum};
+use pfp::config::{self, Config, Format};
+use pfp::Error;
+
+/// Print resolved config (defaults applied, env vars expanded) to stdout
+#[derive(Args)]
+pub(super) struct PrintConfigArgs {
+    /// output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Json)]
+    format: OutputFormat,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum OutputFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+pub(super) fn run(config: &Config, args: PrintConfigArgs) -> Result<(), Error> {
+    let format = match args.format {
+        OutputFormat::Json => Format::Json,
+        OutputFormat::Toml => Format::Toml,
+        OutputFormat::Yaml => Format::Yaml,
+    };
+    println!("{}", config::to_string(config, format)?.trim_end());
+    Ok(())
+}
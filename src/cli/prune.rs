@@ -0,0 +1,55 @@
+use std::io::IsTerminal;
+
+use super::selectors::confirm;
+use clap::Args;
+use log::info;
+use pfp::config::Config;
+use pfp::session::{kill_sessions, stale_sessions};
+use pfp::tmux::execute_tmux_args;
+use pfp::Error;
+
+/// Kill stale sessions: with deleted project dir, idle for too long, or created by pfp and left with an idle shell
+#[derive(Args)]
+pub(super) struct PruneArgs {
+    /// kill stale sessions without confirmation (e.g. when run from cron)
+    #[arg(short, long)]
+    yes: bool,
+    /// sessions with no activity for this many hours are stale, 0 disables the check [default: prune.idle_hours from config]
+    #[arg(long, value_name = "HOURS")]
+    idle_hours: Option<u64>,
+}
+
+pub(super) fn run(config: &Config, args: PruneArgs) -> Result<(), Error> {
+    // nothing to prune if tmux server is not running
+    if !execute_tmux_args(&["list-sessions"])?.status.success() {
+        info!("no tmux sessions");
+        return Ok(());
+    }
+    let stale = stale_sessions(args.idle_hours.unwrap_or(config.prune.idle_hours))?;
+    if stale.is_empty() {
+        info!("no stale sessions");
+        return Ok(());
+    }
+
+    let name_width = stale
+        .iter()
+        .map(|(s, _)| s.name.chars().count())
+        .max()
+        .unwrap_or_default();
+    for (session, reason) in stale.iter() {
+        println!("{:<name_width$}  {}", session.name, reason);
+    }
+    if !args.yes {
+        if !std::io::stdin().is_terminal() {
+            return Err(Error::CmdArg(
+                "use --yes to kill stale sessions without confirmation".to_owned(),
+            ));
+        }
+        if !confirm(&format!("Kill {} session(s)? [y/N]: ", stale.len()))? {
+            return Ok(());
+        }
+    }
+
+    let killed = stale.iter().map(|(s, _)| s.id.as_str()).collect::<Vec<&str>>();
+    kill_sessions(config, &killed)
+}
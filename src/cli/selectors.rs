@@ -0,0 +1,155 @@
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+use log::{debug, trace};
+
+use pfp::config::Config;
+use pfp::discovery::discover_projects;
+use pfp::fs::expand;
+use pfp::Error;
+
+use super::fzf::{execute_fzf_command, PICKER};
+
+/// sets fzf-compatible picker command used by all pickers (e.g. `sk` or `fzf-tmux -p`), fzf is used by default
+/// picker can be set only once, before the first pick
+pub(super) fn set_picker(command: &str) {
+    let command = command
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect::<Vec<String>>();
+    if !command.is_empty() {
+        let _ = PICKER.set(command);
+    }
+}
+
+/// picker args for lists of tmux sessions/windows/panes: lines start with a hidden tab-separated target id,
+/// which is used for capture-pane preview
+pub(super) const TARGET_PICKER_ARGS: [&str; 10] = [
+    "--layout",
+    "reverse",
+    "--delimiter",
+    "\t",
+    "--with-nth",
+    "2..",
+    "--preview",
+    "tmux capture-pane -ept {1}",
+    "--preview-window",
+    "right:nohidden",
+];
+
+/// shortens path in home dir to `~/...` for display
+pub(super) fn tilde_path(path: &str) -> String {
+    let home = std::env::var("HOME").unwrap_or_default();
+    match path.strip_prefix(&home) {
+        Some(rest) if !home.is_empty() => format!("~{}", rest),
+        _ => path.to_owned(),
+    }
+}
+
+/// shows list in fzf and returns picked line(s), fails with [`Error::EmptyPick`] if nothing was picked
+pub(super) fn select_from_list(list: &str, header: &'static str, args: &[&str]) -> Result<String, Error> {
+    let result = execute_fzf_command(args.iter().chain(&["--header", header]).cloned(), list)?;
+    if result.is_empty() {
+        trace!("Empty pick");
+        Err(Error::EmptyPick())
+    } else {
+        trace!("Pick: {}", result);
+        Ok(result)
+    }
+}
+
+/// prints prompt and reads a single line from stdin
+pub(super) fn read_input(prompt: &str) -> Result<String, Error> {
+    print!("{}", prompt);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().lock().read_line(&mut input)?;
+    let input = input.trim().to_owned();
+    if input.is_empty() {
+        trace!("Empty input");
+        Err(Error::EmptyPick())
+    } else {
+        Ok(input)
+    }
+}
+
+/// asks for y/N confirmation, empty answer means no
+pub(super) fn confirm(prompt: &str) -> Result<bool, Error> {
+    match read_input(prompt) {
+        Ok(answer) => Ok(matches!(answer.as_str(), "y" | "Y" | "yes")),
+        Err(Error::EmptyPick()) => Ok(false),
+        Err(err) => Err(err),
+    }
+}
+
+/// shows discovered projects in fzf (with tree preview) and returns the picked path
+pub(super) fn pick_project(config: &Config, header: &'static str) -> Result<String, Error> {
+    pick_project_with_query(config, header, None)
+}
+
+/// shows project picker with the query typed in (if any)
+fn pick_project_with_query(
+    config: &Config,
+    header: &'static str,
+    query: Option<&str>,
+) -> Result<String, Error> {
+    // get dirs' paths
+    let dirs = discover_projects(config)?
+        .into_iter()
+        .map(|project| project.path)
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    // pick one from list with fzf
+    let mut args = vec![
+        "--layout",
+        "reverse",
+        "--preview",
+        "tree -C '{}'",
+        "--preview-window",
+        "right:nohidden",
+    ];
+    if let Some(query) = query {
+        args.extend(["--query", query]);
+    }
+    let pick = select_from_list(&dirs, header, &args)?.trim_end().to_owned();
+    Ok(pick)
+}
+
+/// resolves project path from the query without the picker, if possible:
+/// - existing path is used as is
+/// - otherwise query is fuzzy matched against discovered projects (same as typing it into the picker),
+///   single match, or single match with the exact dir name, is used
+/// - if query is still ambiguous, the best match is used with `first`, otherwise the picker is shown
+pub(super) fn resolve_project(
+    config: &Config,
+    query: &str,
+    header: &'static str,
+    first: bool,
+) -> Result<String, Error> {
+    let expanded = expand(query)?;
+    if Path::new(&expanded).exists() {
+        let path = std::fs::canonicalize(&expanded)?;
+        return Ok(path.to_string_lossy().into_owned());
+    }
+
+    let paths = discover_projects(config)?
+        .into_iter()
+        .map(|project| project.path)
+        .collect::<Vec<String>>()
+        .join("\n");
+    let filtered = execute_fzf_command(["--filter", query].into_iter(), &paths)?;
+    let matches = filtered.lines().collect::<Vec<&str>>();
+    debug!("projects matching {}: {:?}", query, matches);
+    let exact_name = matches
+        .iter()
+        .filter(|path| path.rsplit('/').next() == Some(query))
+        .copied()
+        .collect::<Vec<_>>();
+    match (matches.as_slice(), exact_name.as_slice()) {
+        ([], _) => Err(Error::Project(format!("no project matches {}", query))),
+        ([path], _) | (_, [path]) => Ok(path.to_string()),
+        ([best, ..], _) if first => Ok(best.to_string()),
+        _ => pick_project_with_query(config, header, Some(query)),
+    }
+}
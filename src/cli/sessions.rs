@@ -0,0 +1,151 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::selectors::{pick_project, read_input, select_from_list, tilde_path, TARGET_PICKER_ARGS};
+use clap::Args;
+use pfp::config::Config;
+use pfp::dry_run::shell_quote;
+use pfp::session::{list_sessions, SessionInfo};
+use pfp::tmux::{execute_tmux_args, tmux_output};
+use pfp::Error;
+
+use super::kill_session::confirm_kill;
+use super::new_session::new_session;
+use super::GlobalArgs;
+
+const HEADER: &str =
+    "Active sessions: enter switch, ctrl-x kill, ctrl-r rename, ctrl-d detach others, ctrl-n new session";
+const NEW_SESSION_KEY: &str = "ctrl-n";
+
+/// Show list of active sessions, select one to switch to it
+#[derive(Args)]
+pub(super) struct SessionsArgs {
+    /// print sessions list in picker format (used to reload the picker)
+    #[arg(long, hide = true)]
+    list: bool,
+    /// kill session with the given id (picker action)
+    #[arg(long, hide = true, value_name = "ID")]
+    kill: Option<String>,
+    /// ask for new name of session with the given id (picker action)
+    #[arg(long, hide = true, value_name = "ID")]
+    rename: Option<String>,
+    /// detach clients other than the current one from session with the given id (picker action)
+    #[arg(long, hide = true, value_name = "ID")]
+    detach_others: Option<String>,
+}
+
+pub(super) fn run(config: &Config, global: &GlobalArgs, args: SessionsArgs) -> Result<(), Error> {
+    // picker actions, run by fzf key bindings
+    if args.list {
+        print!("{}", sessions_list(&list_sessions()?));
+        return Ok(());
+    }
+    if let Some(id) = args.kill {
+        return confirm_kill(config, &list_sessions()?, &[id], false);
+    }
+    if let Some(id) = args.rename {
+        let name = tmux_output(&["display-message", "-p", "-t", &id, "#S"])?;
+        let new_name = read_input(&format!("New name for {}: ", name))?;
+        tmux_output(&["rename-session", "-t", &id, &new_name])?;
+        return Ok(());
+    }
+    if let Some(id) = args.detach_others {
+        return detach_others(&id);
+    }
+
+    let current_session = tmux_output(&["display-message", "-p", "#{session_id}"])?;
+    let sessions = list_sessions()?;
+    let idx = sessions.iter().position(|s| s.id == current_session).unwrap_or(0);
+    // picker actions are run with the same global flags (e.g. --dry-run) as the picker itself
+    let exe = std::env::current_exe()?;
+    let mut pfp = vec![exe.to_string_lossy().into_owned()];
+    pfp.extend(global.to_args());
+    pfp.push("sessions".to_owned());
+    let pfp = pfp
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
+    let reload = format!("reload({} --list)", pfp);
+    let pick = select_from_list(
+        &sessions_list(&sessions),
+        HEADER,
+        &[
+            &TARGET_PICKER_ARGS[..],
+            &[
+                "--sync",
+                "--bind",
+                &format!("load:pos({})", idx + 1),
+                "--bind",
+                &format!("ctrl-x:execute({} --kill {{1}})+{}", pfp, reload),
+                "--bind",
+                &format!("ctrl-r:execute({} --rename {{1}})+{}", pfp, reload),
+                "--bind",
+                &format!("ctrl-d:execute-silent({} --detach-others {{1}})+{}", pfp, reload),
+                "--expect",
+                NEW_SESSION_KEY,
+            ],
+        ]
+        .concat(),
+    )?;
+
+    // first line is the key pressed (empty for enter), second one is the picked session
+    let mut lines = pick.lines();
+    let key = lines.next().unwrap_or_default();
+    if key == NEW_SESSION_KEY {
+        let pick = pick_project(config, "New session:")?;
+        return new_session(config, &pick);
+    }
+    match lines.next().and_then(|line| line.split('\t').next()) {
+        Some(id) if !id.is_empty() => {
+            execute_tmux_args(&["switch-client", "-t", id])?;
+            Ok(())
+        }
+        _ => Err(Error::EmptyPick()),
+    }
+}
+
+/// picker lines: hidden session id, then name, windows count, attached clients, last activity and path
+pub(super) fn sessions_list(sessions: &[SessionInfo]) -> String {
+    let name_width = sessions.iter().map(|s| s.name.chars().count()).max().unwrap_or(0);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    sessions
+        .iter()
+        .map(|s| {
+            let attached = match s.attached {
+                0 => "-".to_owned(),
+                n => format!("{} attached", n),
+            };
+            format!(
+                "{}\t{:<name_width$}\t{:>3} windows\t{:<10}\t{:>8}\t{}\n",
+                s.id,
+                s.name,
+                s.windows,
+                attached,
+                ago(now.saturating_sub(s.activity)),
+                tilde_path(&s.path),
+            )
+        })
+        .collect()
+}
+
+/// short relative time, e.g. `5m ago`
+fn ago(seconds: u64) -> String {
+    match seconds {
+        0..=59 => "now".to_owned(),
+        60..=3599 => format!("{}m ago", seconds / 60),
+        3600..=86399 => format!("{}h ago", seconds / 3600),
+        _ => format!("{}d ago", seconds / 86400),
+    }
+}
+
+fn detach_others(id: &str) -> Result<(), Error> {
+    let current_client = tmux_output(&["display-message", "-p", "#{client_name}"])?;
+    let clients = tmux_output(&["list-clients", "-t", id, "-F", "#{client_name}"])?;
+    for client in clients.lines().filter(|c| *c != current_client) {
+        tmux_output(&["detach-client", "-t", client])?;
+    }
+    Ok(())
+}
@@ -0,0 +1,134 @@
+use std::process;
+
+use super::selectors::select_from_list;
+use clap::Args;
+use clap_complete::engine::ArgValueCandidates;
+use pfp::config::{Config, Session};
+use pfp::session::{reconcile_session, start_session, wait_ready, Reconciled};
+use pfp::tmux::{execute_tmux_args, execute_tmux_args_with_stdin};
+use pfp::Error;
+
+use super::session_candidates;
+
+/// Start tmux sessions from predefined list
+#[derive(Args)]
+pub(super) struct StartArgs {
+    /// attach to tmux session after start
+    #[arg(short, long)]
+    attach: bool,
+    /// add windows missing in already running sessions (windows already there are kept) and report them
+    #[arg(short, long)]
+    reconcile: bool,
+    /// start all predefined sessions
+    #[arg(long, conflicts_with = "sessions")]
+    all: bool,
+    /// names or groups of sessions to start, picker is shown if none are given
+    #[arg(value_name = "SESSION", add = ArgValueCandidates::new(session_candidates))]
+    sessions: Vec<String>,
+}
+
+pub(super) fn run(config: &Config, args: StartArgs) -> Result<(), Error> {
+    // inherit stdin to be able to attach
+    let stdin_opt = match args.attach {
+        true => process::Stdio::inherit(),
+        false => process::Stdio::piped(),
+    };
+    let mut names = vec![];
+    for name in args.sessions.iter() {
+        // session names take precedence over groups
+        if let Some(session) = config.sessions.iter().find(|s| &s.name == name) {
+            names.push(session.name.as_str());
+            continue;
+        }
+        let group = config
+            .sessions
+            .iter()
+            .filter(|s| s.groups.contains(name))
+            .map(|s| s.name.as_str())
+            .collect::<Vec<&str>>();
+        if group.is_empty() {
+            return Err(Error::CmdArg(format!("unknown session or group {}", name)));
+        }
+        names.extend(group);
+    }
+    // nothing to pick from, just start tmux (given names are checked above, so they are not silently ignored)
+    if config.sessions.is_empty() && names.is_empty() && !args.all {
+        execute_tmux_args_with_stdin(&[], stdin_opt)?;
+        return Ok(());
+    }
+    // list is empty if tmux server is not running
+    let sessions = String::from_utf8(execute_tmux_args(&["list-sessions", "-F", "#S"])?.stdout)?;
+    let all_names = config
+        .sessions
+        .iter()
+        .map(|s| s.name.as_str())
+        .collect::<Vec<&str>>()
+        .join("\n");
+    let pick = match (args.all, names.is_empty()) {
+        (true, _) => all_names,
+        (false, false) => names.join("\n"),
+        (false, true) => select_from_list(
+            &all_names,
+            "Start sessions:",
+            &[
+                "-m",
+                "--layout",
+                "reverse",
+                "--preview",
+                &format!(
+                    "echo '{}'",
+                    config
+                        .sessions
+                        .iter()
+                        .map(Session::to_string)
+                        .collect::<Vec<_>>()
+                        .join("\n")
+                ),
+                "--preview-window",
+                "right:nohidden",
+            ],
+        )?,
+    };
+    let picked_sessions = pick.split('\n').filter(|x| !x.is_empty()).collect::<Vec<&str>>();
+    let picked_names = config
+        .sessions
+        .iter()
+        .map(|s| s.name.as_str())
+        .filter(|name| picked_sessions.contains(name))
+        .collect::<Vec<&str>>();
+    // sessions are started after sessions they depend on (which are started even if not picked)
+    let order = config.start_order(&picked_names)?;
+    for session in order.iter() {
+        let session_exists = sessions
+            .split('\n')
+            .find(|x| *x == session.name)
+            .map(|_| true)
+            .unwrap_or(false);
+        match (session_exists, args.reconcile) {
+            (true, true) => report(&session.name, &reconcile_session(config, session)?),
+            (true, false) => println!("session {} exists", session.name),
+            (false, _) => start_session(config, session)?,
+        }
+        // dependents are started only once the session is ready
+        if order.iter().any(|s| s.depends_on.contains(&session.name)) {
+            wait_ready(session)?;
+        }
+    }
+    execute_tmux_args_with_stdin(&["attach"], stdin_opt)?;
+    Ok(())
+}
+
+/// prints windows added to the running session (`+`) and windows not declared in config (`?`)
+fn report(session_name: &str, reconciled: &Reconciled) {
+    if reconciled.added.is_empty() && reconciled.extra.is_empty() {
+        println!("session {} is up to date", session_name);
+        return;
+    }
+    println!("session {}:", session_name);
+    for (name, path) in reconciled.added.iter() {
+        println!("  + {} {}", name, path);
+    }
+    for name in reconciled.extra.iter() {
+        println!("  ? {} (not in config, kept)", name);
+    }
+}
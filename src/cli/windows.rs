@@ -0,0 +1,55 @@
+use super::selectors::{select_from_list, tilde_path, TARGET_PICKER_ARGS};
+use clap::Args;
+use pfp::config::Config;
+use pfp::session::{list_panes, list_windows, switch_to_target, TargetInfo};
+use pfp::Error;
+
+/// Show windows of all sessions, select one to switch to it
+#[derive(Args)]
+pub(super) struct WindowsArgs {}
+
+/// Show panes of all sessions, select one to switch to it
+#[derive(Args)]
+pub(super) struct PanesArgs {}
+
+pub(super) fn run_windows(_config: &Config, _args: WindowsArgs) -> Result<(), Error> {
+    pick_target(&list_windows()?, "Windows:")
+}
+
+pub(super) fn run_panes(_config: &Config, _args: PanesArgs) -> Result<(), Error> {
+    pick_target(&list_panes()?, "Panes:")
+}
+
+/// shows targets with their running commands and dirs, with capture-pane preview, and switches to the picked one
+fn pick_target(targets: &[TargetInfo], header: &'static str) -> Result<(), Error> {
+    let target_width = targets
+        .iter()
+        .map(|t| t.target.chars().count())
+        .max()
+        .unwrap_or(0);
+    let name_width = targets.iter().map(|t| t.name.chars().count()).max().unwrap_or(0);
+    let command_width = targets
+        .iter()
+        .map(|t| t.command.chars().count())
+        .max()
+        .unwrap_or(0);
+    let list = targets
+        .iter()
+        .map(|t| {
+            format!(
+                "{}\t{:<target_width$}\t{:<name_width$}\t{:<command_width$}\t{}",
+                t.id,
+                t.target,
+                t.name,
+                t.command,
+                tilde_path(&t.path)
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n");
+    let pick = select_from_list(&list, header, &TARGET_PICKER_ARGS)?;
+    match pick.split('\t').next() {
+        Some(id) if !id.trim().is_empty() => switch_to_target(id.trim()),
+        _ => Err(Error::EmptyPick()),
+    }
+}
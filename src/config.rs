@@ -1,49 +1,304 @@
-use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+use serde::de::{MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_jsonc::{Map, Value};
+
+pub const SYSTEM_CONFIG_PATH: &str = "/etc/pfp/config.json";
 
 #[derive(thiserror::Error, Debug)]
-pub(crate) enum ConfigError {
+#[non_exhaustive]
+pub enum ConfigError {
     #[error("Parse config: {0}")]
     Parse(#[from] serde_jsonc::Error),
     #[error("Read config: {0}")]
     Read(#[from] std::io::Error),
+    #[error("Import config: {0}")]
+    Import(String),
+    #[error("{0} issue(s) found")]
+    Invalid(usize),
+    #[error("Parse config: {0}")]
+    ParseToml(#[from] toml::de::Error),
+    #[error("Parse config: {0}")]
+    ParseYaml(#[from] serde_norway::Error),
+    #[error("Serialize config: {0}")]
+    Serialize(String),
+    #[error("Session dependencies: {0}")]
+    Dependency(String),
+}
+
+/// Config file format, detected by file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Toml,
+    Yaml,
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct Config<'a> {
+impl Format {
+    /// `.toml` -> TOML, `.yaml`/`.yml` -> YAML, anything else (`.json`, `.jsonc`) -> JSONC
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Format::Toml,
+            Some("yaml" | "yml") => Format::Yaml,
+            _ => Format::Json,
+        }
+    }
+}
+
+/// parses config text of the given format into JSON value (which is then merged with other layers)
+pub fn parse_value(text: &str, format: Format) -> Result<Value, ConfigError> {
+    Ok(match format {
+        Format::Json => serde_jsonc::from_str(text)?,
+        Format::Toml => toml::from_str(text)?,
+        Format::Yaml => serde_norway::from_str(text)?,
+    })
+}
+
+/// serializes resolved config (defaults applied, env vars in paths expanded) into the given format
+/// paths with unset env vars are kept as is (`check-config` reports them)
+pub fn to_string(config: &Config, format: Format) -> Result<String, ConfigError> {
+    let mut value = serde_jsonc::to_value(config).map_err(|e| ConfigError::Serialize(e.to_string()))?;
+    for (list, field) in [
+        ("include", "paths"),
+        ("sessions", "windows"),
+        ("templates", "path"),
+    ] {
+        for entry in value
+            .get_mut(list)
+            .and_then(Value::as_array_mut)
+            .into_iter()
+            .flatten()
+        {
+            let paths = match entry.get_mut(field) {
+                Some(Value::Array(paths)) => paths.iter_mut().collect(),
+                Some(path) => vec![path],
+                None => vec![],
+            };
+            for path in paths {
+                if let Value::String(path) = path {
+                    if let Ok(expanded) = crate::fs::expand(path) {
+                        *path = expanded;
+                    }
+                }
+            }
+        }
+    }
+    match format {
+        Format::Json => {
+            serde_jsonc::to_string_pretty(&value).map_err(|e| ConfigError::Serialize(e.to_string()))
+        }
+        Format::Toml => toml::to_string_pretty(&value).map_err(|e| ConfigError::Serialize(e.to_string())),
+        Format::Yaml => serde_norway::to_string(&value).map_err(|e| ConfigError::Serialize(e.to_string())),
+    }
+}
+
+/// JSON schema of the config file
+pub fn schema() -> Result<String, ConfigError> {
+    serde_jsonc::to_string_pretty(&schemars::schema_for!(Config))
+        .map_err(|e| ConfigError::Serialize(e.to_string()))
+}
+
+/// Pfp config, merged from all config layers (see [`read_config`])
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Config {
+    /// config files merged before this one, paths are relative to the file's dir
+    /// (imports are resolved while reading layers, so they are always empty in the merged config)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub imports: Vec<String>,
+    #[serde(default)]
+    pub sessions: Vec<Session>,
+    #[serde(default, deserialize_with = "legacy_shape")]
+    #[schemars(with = "LegacyShapeSchema<Markers>")]
+    pub markers: Markers,
+    #[serde(default, deserialize_with = "legacy_shape")]
+    #[schemars(with = "LegacyShapeSchema<Ignore>")]
+    pub ignore: Ignore,
+    #[serde(default = "default_include")]
+    pub include: Vec<IncludeEntry>,
     #[serde(default)]
-    pub sessions: Vec<Session<'a>>,
-    #[serde(default, borrow = "'a")]
-    pub markers: Markers<'a>,
+    pub templates: Vec<Template>,
     #[serde(default)]
-    pub ignore: Ignore<'a>,
-    pub include: Vec<IncludeEntry<'a>>,
+    pub hooks: Hooks,
+    #[serde(default)]
+    pub project_types: Vec<ProjectType>,
+    #[serde(default)]
+    pub prune: Prune,
 }
 
-impl<'a> Default for Config<'a> {
+fn default_include() -> Vec<IncludeEntry> {
+    vec![IncludeEntry {
+        paths: vec!["$HOME".to_owned()],
+        ..Default::default()
+    }]
+}
+
+impl Default for Config {
     fn default() -> Self {
         Self {
+            imports: vec![],
             sessions: vec![],
             markers: Markers::default(),
             ignore: Ignore::default(),
-            include: vec![IncludeEntry {
-                paths: ["$HOME"].to_vec(),
-                ..Default::default()
-            }],
+            include: default_include(),
+            templates: vec![],
+            hooks: Hooks::default(),
+            project_types: vec![],
+            prune: Prune::default(),
+        }
+    }
+}
+
+impl Config {
+    /// predefined sessions to start for the given session names, each one preceded by sessions it depends on
+    pub fn start_order(&self, names: &[&str]) -> Result<Vec<&Session>, ConfigError> {
+        let mut order = vec![];
+        for name in names {
+            self.visit_session(name, &mut vec![], &mut order)?;
+        }
+        Ok(order)
+    }
+
+    /// depth-first walk over `depends_on`, `stack` holds sessions being visited to detect dependency cycles
+    fn visit_session<'a>(
+        &'a self,
+        name: &str,
+        stack: &mut Vec<&'a str>,
+        order: &mut Vec<&'a Session>,
+    ) -> Result<(), ConfigError> {
+        if order.iter().any(|s| s.name == name) {
+            return Ok(());
+        }
+        let Some(session) = self.sessions.iter().find(|s| s.name == name) else {
+            return Err(ConfigError::Dependency(match stack.last() {
+                Some(parent) => format!("session {} depends on unknown session {}", parent, name),
+                None => format!("unknown session {}", name),
+            }));
+        };
+        if stack.contains(&session.name.as_str()) {
+            return Err(ConfigError::Dependency(format!(
+                "dependency cycle {} -> {}",
+                stack.join(" -> "),
+                name
+            )));
+        }
+        stack.push(&session.name);
+        for dependency in session.depends_on.iter() {
+            self.visit_session(dependency, stack, order)?;
+        }
+        stack.pop();
+        order.push(session);
+        Ok(())
+    }
+
+    /// collects hooks applicable to the project path:
+    /// root hooks, hooks of the include entry the path belongs to, and hooks of all matching project types
+    pub fn hooks_for(&self, path: &str) -> Vec<&Hooks> {
+        let mut hooks = vec![&self.hooks];
+
+        // include entry with the longest root containing the path
+        let include_entry = self
+            .include
+            .iter()
+            .flat_map(|entry| entry.paths.iter().map(move |p| (entry, p)))
+            .filter_map(|(entry, p)| {
+                let root = crate::fs::expand(p).ok()?;
+                let root = root.trim_end_matches('/');
+                (path == root || path.starts_with(&format!("{}/", root))).then_some((entry, root.len()))
+            })
+            .max_by_key(|(_, len)| *len)
+            .map(|(entry, _)| entry);
+        if let Some(include_entry) = include_entry {
+            hooks.push(&include_entry.hooks);
         }
+
+        let dir = match crate::fs::path_is_file(path) {
+            true => std::path::Path::new(path)
+                .parent()
+                .unwrap_or(std::path::Path::new(path)),
+            false => std::path::Path::new(path),
+        };
+        for project_type in self.project_types.iter() {
+            if project_type.markers.iter().any(|m| dir.join(m).exists()) {
+                log::trace!("project type {} matched for {}", project_type.name, path);
+                hooks.push(&project_type.hooks);
+            }
+        }
+        hooks
     }
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct Session<'a> {
-    pub name: &'a str,
-    pub windows: Vec<&'a str>,
+/// Predefined session for `start` subcommand, with a window for each of `windows` paths
+/// `groups` are tags (e.g. "work") that start all sessions having them at once
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Session {
+    pub name: String,
+    pub windows: Vec<String>,
+    #[serde(default)]
+    pub groups: Vec<String>,
+    /// sessions started (and waited to be ready) before this one
+    #[serde(default)]
+    pub depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready: Option<ReadyCheck>,
+}
+
+fn default_ready_host() -> String {
+    "localhost".to_owned()
 }
 
-impl<'a> ToString for Session<'a> {
-    fn to_string(&self) -> String {
-        format!(
-            "{}:\n{}\n",
+fn default_ready_timeout() -> u64 {
+    60
+}
+
+/// Readiness check of the session, sessions depending on it are started once all given checks pass:
+/// `port` accepts connections on `host`, `file` exists, `command` exits successfully (run with `sh -c` in session's first window dir).
+/// Checks are retried until `timeout` (in seconds) is over.
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct ReadyCheck {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(default = "default_ready_host")]
+    pub host: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default = "default_ready_timeout")]
+    pub timeout: u64,
+}
+
+impl std::fmt::Display for ReadyCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut checks = vec![];
+        if let Some(port) = self.port {
+            checks.push(format!("port {}:{} is open", self.host, port));
+        }
+        if let Some(file) = &self.file {
+            checks.push(format!("file {} exists", file));
+        }
+        if let Some(command) = &self.command {
+            checks.push(format!("command `{}` succeeds", command));
+        }
+        write!(f, "{}", checks.join(", "))
+    }
+}
+
+impl std::fmt::Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let groups = match self.groups.is_empty() {
+            true => String::new(),
+            false => format!(" [{}]", self.groups.join(", ")),
+        };
+        write!(
+            f,
+            "{}{}:\n{}\n",
             self.name,
+            groups,
             self.windows
                 .iter()
                 .map(|p| crate::fs::expand(p).unwrap_or(p.to_string()))
@@ -53,6 +308,104 @@ impl<'a> ToString for Session<'a> {
     }
 }
 
+/// Template for `new-project` subcommand.
+/// Contents of `path` dir (if any) are copied into the new project dir, then `init` commands are run inside it.
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Template {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub init: Vec<String>,
+}
+
+/// Per-project config, read from `.pfp.json` file in the project dir (JSONC, same as the main config).
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Default)]
+pub struct ProjectConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_name: Option<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub windows: Vec<Window>,
+}
+
+impl ProjectConfig {
+    /// whether the config declares any shell commands to run
+    pub fn has_commands(&self) -> bool {
+        self.windows
+            .iter()
+            .any(|w| w.command.is_some() || w.panes.iter().any(|p| p.command.is_some()))
+    }
+}
+
+/// Window of the session. `path` is relative to the project dir (project dir itself if not set),
+/// `command` is typed into the window's first pane, `layout` is any of tmux `select-layout` layouts.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Default)]
+pub struct Window {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+    #[serde(default)]
+    pub panes: Vec<Pane>,
+}
+
+/// Additional pane of the window, split from its first pane. `path` is relative to the window's dir.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Default)]
+pub struct Pane {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// Shell commands run on session/window lifecycle events.
+/// Commands are run with `sh -c` in the project dir, with `PFP_PROJECT_PATH` and `PFP_SESSION_NAME`
+/// (and `PFP_WINDOW_NAME` for `on_window_create`) env vars set.
+/// `on_window_create` is not run for the first window of a session, use `on_session_create` for it.
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Default)]
+pub struct Hooks {
+    #[serde(default)]
+    pub on_session_create: Vec<String>,
+    #[serde(default)]
+    pub on_window_create: Vec<String>,
+    #[serde(default)]
+    pub on_session_kill: Vec<String>,
+}
+
+/// Project type is detected by presence of any of `markers` in the project dir.
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct ProjectType {
+    pub name: String,
+    pub markers: Vec<String>,
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+fn default_idle_hours() -> u64 {
+    72
+}
+
+/// Settings of `prune` subcommand: sessions with no activity for `idle_hours` are considered stale (0 disables the check)
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Prune {
+    #[serde(default = "default_idle_hours")]
+    pub idle_hours: u64,
+}
+
+impl Default for Prune {
+    fn default() -> Self {
+        Self {
+            idle_hours: default_idle_hours(),
+        }
+    }
+}
+
 fn default_yield_on_marker() -> bool {
     true
 }
@@ -61,25 +414,32 @@ fn default_include_intermediate_paths() -> bool {
     true
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct IncludeEntry<'a> {
-    #[serde(borrow = "'a")]
-    pub paths: Vec<&'a str>,
+fn default_depth() -> u8 {
+    u8::MAX
+}
+
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct IncludeEntry {
+    pub paths: Vec<String>,
     #[serde(default)]
     pub mode: Mode,
-    #[serde(default)]
-    pub markers: Markers<'a>,
-    #[serde(default)]
-    pub ignore: Ignore<'a>,
+    #[serde(default, deserialize_with = "legacy_shape")]
+    #[schemars(with = "LegacyShapeSchema<Markers>")]
+    pub markers: Markers,
+    #[serde(default, deserialize_with = "legacy_shape")]
+    #[schemars(with = "LegacyShapeSchema<Ignore>")]
+    pub ignore: Ignore,
     #[serde(default = "default_include_intermediate_paths")]
     pub include_intermediate_paths: bool,
     #[serde(default = "default_yield_on_marker")]
     pub yield_on_marker: bool,
-    #[serde(default = "u8::max_value")]
+    #[serde(default = "default_depth")]
     pub depth: u8,
+    #[serde(default)]
+    pub hooks: Hooks,
 }
 
-impl<'a> Default for IncludeEntry<'a> {
+impl Default for IncludeEntry {
     fn default() -> Self {
         Self {
             paths: vec![],
@@ -88,20 +448,81 @@ impl<'a> Default for IncludeEntry<'a> {
             ignore: Ignore::default(),
             include_intermediate_paths: default_include_intermediate_paths(),
             yield_on_marker: default_yield_on_marker(),
-            depth: u8::max_value(),
+            depth: default_depth(),
+            hooks: Hooks::default(),
         }
     }
 }
 
-#[derive(Deserialize, Debug, Default)]
+#[derive(Deserialize, Serialize, JsonSchema, Debug, Default)]
 #[serde(rename_all = "snake_case")]
 // #[serde(untagged)]
-pub(crate) enum Mode {
+pub enum Mode {
     #[default]
     Dir,
     File,
 }
 
+/// `markers` and `ignore` used to be flat lists of exact names, both shapes are accepted
+/// (`config migrate` rewrites the legacy shape into the current one)
+trait LegacyShape: for<'de> Deserialize<'de> {
+    /// value built from the legacy flat list
+    fn from_exact(exact: Vec<String>) -> Self;
+}
+
+impl LegacyShape for Markers {
+    fn from_exact(exact: Vec<String>) -> Self {
+        Markers {
+            exact,
+            pattern: vec![],
+            ..Markers::default()
+        }
+    }
+}
+
+impl LegacyShape for Ignore {
+    fn from_exact(exact: Vec<String>) -> Self {
+        Ignore {
+            exact,
+            pattern: vec![],
+            ..Ignore::default()
+        }
+    }
+}
+
+/// dispatches on the shape of the value, unlike `#[serde(untagged)]` it doesn't buffer the value,
+/// so that unknown keys are still reported (see `check-config`) and errors point at the actual problem
+struct LegacyShapeVisitor<T>(PhantomData<T>);
+
+impl<'de, T: LegacyShape> Visitor<'de> for LegacyShapeVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "a list of names or an object")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<T, A::Error> {
+        Ok(T::from_exact(Vec::deserialize(SeqAccessDeserializer::new(seq))?))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<T, A::Error> {
+        T::deserialize(MapAccessDeserializer::new(map))
+    }
+}
+
+/// schema of fields accepting both shapes: legacy list of exact names or the current object
+#[derive(JsonSchema)]
+#[serde(untagged)]
+#[allow(dead_code)]
+enum LegacyShapeSchema<T> {
+    Flat(Vec<String>),
+    Current(T),
+}
+
+fn legacy_shape<'de, D: Deserializer<'de>, T: LegacyShape>(deserializer: D) -> Result<T, D::Error> {
+    deserializer.deserialize_any(LegacyShapeVisitor(PhantomData))
+}
+
 const MARKERS_EXACT_DEFAULT: [&str; 3] = [
     ".git",
     "Cargo.toml",
@@ -121,23 +542,23 @@ fn default_chain_root_markers() -> bool {
     true
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct Markers<'a> {
-    #[serde(default, borrow = "'a")]
-    pub exact: Vec<&'a str>,
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Markers {
+    #[serde(default)]
+    pub exact: Vec<String>,
     #[serde(default)]
-    pub pattern: Vec<&'a str>,
+    pub pattern: Vec<String>,
     #[serde(default = "default_traverse_hidden")]
     pub traverse_hidden: bool,
     #[serde(default = "default_chain_root_markers")]
     pub chain_root_markers: bool,
 }
 
-impl<'a> Default for Markers<'a> {
+impl Default for Markers {
     fn default() -> Self {
         Markers {
-            exact: Vec::from(MARKERS_EXACT_DEFAULT),
-            pattern: Vec::from(MARKERS_PATTERN_DEFAULT),
+            exact: MARKERS_EXACT_DEFAULT.map(String::from).to_vec(),
+            pattern: MARKERS_PATTERN_DEFAULT.map(String::from).to_vec(),
             chain_root_markers: default_chain_root_markers(),
             traverse_hidden: default_traverse_hidden(),
         }
@@ -164,27 +585,318 @@ fn default_chain_root_ignore() -> bool {
     true
 }
 
-#[derive(Deserialize, Debug)]
-pub(crate) struct Ignore<'a> {
-    #[serde(default, borrow = "'a")]
-    pub exact: Vec<&'a str>,
+#[derive(Deserialize, Serialize, JsonSchema, Debug)]
+pub struct Ignore {
     #[serde(default)]
-    pub pattern: Vec<&'a str>,
+    pub exact: Vec<String>,
+    #[serde(default)]
+    pub pattern: Vec<String>,
     #[serde(default = "default_chain_root_ignore")]
     pub chain_root_ignore: bool,
 }
 
-impl<'a> Default for Ignore<'a> {
+impl Default for Ignore {
     fn default() -> Self {
         Ignore {
-            exact: Vec::from(IGNORE_EXACT_DEFAULT),
-            pattern: Vec::from(IGNORE_PATTERN_DEFAULT),
+            exact: IGNORE_EXACT_DEFAULT.map(String::from).to_vec(),
+            pattern: IGNORE_PATTERN_DEFAULT.map(String::from).to_vec(),
             chain_root_ignore: default_chain_root_ignore(),
         }
     }
 }
 
-pub(crate) fn read_config(path: &str) -> Result<Config, ConfigError> {
-    let contents = Box::leak(Box::new(std::fs::read_to_string(path)?));
-    Ok(serde_jsonc::from_str(contents)?)
+/// reads config layers and merges them in order, so that later layers take precedence
+/// `imports` of each layer are merged right before the layer itself
+/// returns default config if there are no layers
+pub fn read_config(paths: &[String]) -> Result<Config, ConfigError> {
+    if paths.is_empty() {
+        return Ok(Config::default());
+    }
+    let mut config = Value::Object(Map::new());
+    for path in paths {
+        read_layer(Path::new(path), &mut config, &mut vec![])?;
+    }
+    Ok(serde_jsonc::from_value(config)?)
+}
+
+/// merges config file (preceded by its imports) into `config`
+/// `stack` holds files being imported, to detect import cycles
+fn read_layer(path: &Path, config: &mut Value, stack: &mut Vec<PathBuf>) -> Result<(), ConfigError> {
+    let path = path.canonicalize().map_err(|e| match stack.is_empty() {
+        // top-level layer (given by --config or found in default locations)
+        true => ConfigError::Read(std::io::Error::new(
+            e.kind(),
+            format!("{}: {}", path.display(), e),
+        )),
+        false => ConfigError::Import(format!("{}: {}", path.display(), e)),
+    })?;
+    if stack.contains(&path) {
+        return Err(ConfigError::Import(format!("import cycle at {}", path.display())));
+    }
+    let mut layer = parse_value(&std::fs::read_to_string(&path)?, Format::from_path(&path))?;
+
+    stack.push(path.clone());
+    if let Some(Value::Array(imports)) = layer.as_object_mut().and_then(|l| l.remove("imports")) {
+        let dir = path.parent().unwrap_or(Path::new("/"));
+        for import in imports.iter().filter_map(Value::as_str) {
+            let import = crate::fs::expand(import).map_err(|e| ConfigError::Import(e.to_string()))?;
+            read_layer(&dir.join(import), config, stack)?;
+        }
+    }
+    stack.pop();
+
+    normalize_layer(&mut layer);
+    merge_layer(config, layer);
+    Ok(())
+}
+
+/// rewrites legacy flat `markers`/`ignore` lists of the layer into `{ "exact": [...] }` objects,
+/// so that they are merged with the same sections of other layers instead of replacing them
+fn normalize_layer(layer: &mut Value) {
+    for section in ["markers", "ignore"] {
+        if let Some(value) = layer.get_mut(section).filter(|v| v.is_array()) {
+            let exact = value.take();
+            *value = Value::Object(Map::from_iter([("exact".to_owned(), exact)]));
+        }
+    }
+}
+
+/// merges config layer into the config built from the previous layers:
+/// - `sessions`, `templates`, `project_types` are merged by name, later entry replaces earlier one with the same name
+/// - `include` entries are appended
+/// - `markers`, `ignore`, `hooks`, `prune` are merged key by key: lists are appended (without duplicates), other values are replaced
+/// - any other key is replaced
+fn merge_layer(config: &mut Value, layer: Value) {
+    let (Value::Object(config), Value::Object(layer)) = (config, layer) else {
+        return;
+    };
+    for (key, value) in layer {
+        match (key.as_str(), config.get_mut(&key), value) {
+            ("sessions" | "templates" | "project_types", Some(Value::Array(base)), Value::Array(entries)) => {
+                for entry in entries {
+                    match base
+                        .iter_mut()
+                        .find(|b| b.get("name").is_some() && b.get("name") == entry.get("name"))
+                    {
+                        Some(b) => *b = entry,
+                        None => base.push(entry),
+                    }
+                }
+            }
+            ("include", Some(Value::Array(base)), Value::Array(entries)) => base.extend(entries),
+            ("markers" | "ignore" | "hooks" | "prune", Some(Value::Object(base)), Value::Object(section)) => {
+                for (k, v) in section {
+                    match (base.get_mut(&k), v) {
+                        (Some(Value::Array(list)), Value::Array(items)) => {
+                            for item in items {
+                                if !list.contains(&item) {
+                                    list.push(item);
+                                }
+                            }
+                        }
+                        (_, v) => {
+                            base.insert(k, v);
+                        }
+                    }
+                }
+            }
+            (_, _, value) => {
+                config.insert(key, value);
+            }
+        }
+    }
+}
+
+/// rewrites legacy flat `markers`/`ignore` lists (root and include entries' ones) into `{ "exact": [...] }` objects
+/// the rest of the text (including comments) is kept as is
+/// returns None if there is nothing to migrate
+pub fn migrate_config(text: &str) -> Result<Option<String>, ConfigError> {
+    let config: Value = serde_jsonc::from_str(text)?;
+    let mut keys = vec![];
+    for section in ["markers", "ignore"] {
+        if config.get(section).is_some_and(Value::is_array) {
+            keys.push(section.to_owned());
+        }
+        let entries = config
+            .get("include")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten();
+        for (i, entry) in entries.enumerate() {
+            if entry.get(section).is_some_and(Value::is_array) {
+                keys.push(format!("include.{}.{}", i, section));
+            }
+        }
+    }
+    if keys.is_empty() {
+        return Ok(None);
+    }
+
+    let spans = crate::jsonc::spans(text);
+    let mut ranges = keys
+        .iter()
+        .filter_map(|key| spans.values.get(key).cloned())
+        .collect::<Vec<_>>();
+    // replace from the end, so that ranges of preceding values stay valid
+    ranges.sort_by_key(|range| std::cmp::Reverse(range.start));
+    let mut migrated = text.to_owned();
+    for range in ranges {
+        let list = &text[range.clone()];
+        migrated.replace_range(range, &format!("{{ \"exact\": {} }}", list));
+    }
+    Ok(Some(migrated))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn migrate_keeps_comments_and_rewrites_nested_entries() {
+        let text = r#"{
+  // root markers
+  "markers": [".git", /* rust */ "Cargo.toml"],
+  "include": [
+    { "paths": ["$HOME"], "ignore": ["target"] },
+    { "paths": ["/work \"x\""], "markers": { "exact": ["go.mod"] } }
+  ]
+}"#;
+        let expected = r#"{
+  // root markers
+  "markers": { "exact": [".git", /* rust */ "Cargo.toml"] },
+  "include": [
+    { "paths": ["$HOME"], "ignore": { "exact": ["target"] } },
+    { "paths": ["/work \"x\""], "markers": { "exact": ["go.mod"] } }
+  ]
+}"#;
+        assert_eq!(migrate_config(text).unwrap().as_deref(), Some(expected));
+    }
+
+    #[test]
+    fn migrate_already_migrated() {
+        for text in [
+            "{}",
+            r#"{ "markers": { "exact": [".git"] }, "ignore": { "pattern": ["^\\."] } }"#,
+            r#"{ "include": [{ "paths": ["/a"], "markers": { "exact": [] } }] }"#,
+        ] {
+            assert_eq!(migrate_config(text).unwrap(), None, "{}", text);
+        }
+    }
+
+    fn json(text: &str) -> Value {
+        serde_jsonc::from_str(text).unwrap()
+    }
+
+    #[test]
+    fn merge_layers() {
+        for (base, layer, expected) in [
+            // sessions, templates and project types are merged by name
+            (
+                r#"{"sessions": [{"name": "a", "windows": ["/a"]}, {"name": "b", "windows": []}]}"#,
+                r#"{"sessions": [{"name": "a", "windows": ["/x"]}, {"name": "c", "windows": []}]}"#,
+                r#"{"sessions": [{"name": "a", "windows": ["/x"]}, {"name": "b", "windows": []}, {"name": "c", "windows": []}]}"#,
+            ),
+            (
+                r#"{"templates": [{"name": "rust"}]}"#,
+                r#"{"templates": [{"name": "rust", "init": ["cargo init"]}]}"#,
+                r#"{"templates": [{"name": "rust", "init": ["cargo init"]}]}"#,
+            ),
+            // include entries are appended
+            (
+                r#"{"include": [{"paths": ["/a"]}]}"#,
+                r#"{"include": [{"paths": ["/a"]}]}"#,
+                r#"{"include": [{"paths": ["/a"]}, {"paths": ["/a"]}]}"#,
+            ),
+            // markers, ignore, hooks and prune are merged key by key, lists are appended without duplicates
+            (
+                r#"{"markers": {"exact": [".git"], "traverse_hidden": true}}"#,
+                r#"{"markers": {"exact": [".git", "go.mod"], "traverse_hidden": false}}"#,
+                r#"{"markers": {"exact": [".git", "go.mod"], "traverse_hidden": false}}"#,
+            ),
+            (
+                r#"{"hooks": {"on_session_create": ["a"]}}"#,
+                r#"{"hooks": {"on_session_kill": ["b"]}}"#,
+                r#"{"hooks": {"on_session_create": ["a"], "on_session_kill": ["b"]}}"#,
+            ),
+            (
+                r#"{"prune": {"idle_hours": 1}}"#,
+                r#"{"prune": {"idle_hours": 2}}"#,
+                r#"{"prune": {"idle_hours": 2}}"#,
+            ),
+            // legacy flat lists are normalized before merging
+            (
+                r#"{"ignore": {"exact": ["target"]}}"#,
+                r#"{"ignore": ["venv"]}"#,
+                r#"{"ignore": {"exact": ["target", "venv"]}}"#,
+            ),
+            // sections missing in base and other keys are replaced
+            (
+                r#"{"project_types": []}"#,
+                r#"{"markers": [".git"], "project_types": {}}"#,
+                r#"{"markers": {"exact": [".git"]}, "project_types": {}}"#,
+            ),
+        ] {
+            let mut config = json(base);
+            let mut layer = json(layer);
+            normalize_layer(&mut layer);
+            merge_layer(&mut config, layer);
+            assert_eq!(config, json(expected), "base: {}", base);
+        }
+    }
+
+    #[test]
+    fn start_order() {
+        let config: Config = serde_jsonc::from_str(
+            r#"{"sessions": [
+                {"name": "web", "windows": [], "depends_on": ["api", "db"]},
+                {"name": "api", "windows": [], "depends_on": ["db"]},
+                {"name": "db", "windows": []},
+                {"name": "docs", "windows": []},
+                {"name": "a", "windows": [], "depends_on": ["b"]},
+                {"name": "b", "windows": [], "depends_on": ["a"]},
+                {"name": "self", "windows": [], "depends_on": ["self"]},
+                {"name": "broken", "windows": [], "depends_on": ["missing"]}
+            ]}"#,
+        )
+        .unwrap();
+        for (names, expected) in [
+            (vec!["web"], Ok(vec!["db", "api", "web"])),
+            (vec!["docs", "api", "web"], Ok(vec!["docs", "db", "api", "web"])),
+            (vec!["db", "db"], Ok(vec!["db"])),
+            (vec![], Ok(vec![])),
+            (
+                vec!["a"],
+                Err("Session dependencies: dependency cycle a -> b -> a"),
+            ),
+            (
+                vec!["self"],
+                Err("Session dependencies: dependency cycle self -> self"),
+            ),
+            (
+                vec!["broken"],
+                Err("Session dependencies: session broken depends on unknown session missing"),
+            ),
+            (vec!["nope"], Err("Session dependencies: unknown session nope")),
+        ] {
+            let order = config
+                .start_order(&names)
+                .map(|order| order.iter().map(|s| s.name.as_str()).collect::<Vec<&str>>())
+                .map_err(|err| err.to_string());
+            assert_eq!(order, expected.map_err(str::to_owned), "{:?}", names);
+        }
+    }
+
+    #[test]
+    fn missing_layer_errors() {
+        let dir = std::env::temp_dir().join(format!("pfp-config-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let layer = dir.join("config.json");
+        std::fs::write(&layer, r#"{"imports": ["missing.json"]}"#).unwrap();
+
+        let top = read_config(&[dir.join("nope.json").to_string_lossy().to_string()]);
+        assert!(matches!(top, Err(ConfigError::Read(_))), "{:?}", top);
+        let import = read_config(&[layer.to_string_lossy().to_string()]);
+        assert!(matches!(import, Err(ConfigError::Import(_))), "{:?}", import);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
@@ -0,0 +1,156 @@
+use std::path::{Path, PathBuf};
+use std::process;
+
+use crate::Error;
+
+/// fzf versions before it don't support `load` event and `pos` action used by the sessions picker
+pub const MIN_FZF_VERSION: (u32, u32) = (0, 36);
+
+/// External binary pfp runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dependency {
+    Tmux,
+    /// fzf or fzf-compatible picker given by its command
+    Picker,
+    /// used for previews of project dirs in the picker
+    Tree,
+    /// used to open files picked with `file` mode include entries
+    Editor,
+}
+
+/// Result of looking up a dependency
+#[derive(Debug)]
+pub struct DependencyStatus {
+    pub dependency: Dependency,
+    /// binary name
+    pub name: String,
+    /// full path of the binary, None if it is not found
+    pub path: Option<PathBuf>,
+    /// first line of the binary's version output
+    pub version: Option<String>,
+    /// what's wrong and how to fix it, None if dependency is fine
+    pub problem: Option<String>,
+}
+
+impl Dependency {
+    /// pfp can't work without required dependencies, others only disable some features
+    pub fn is_required(&self) -> bool {
+        matches!(self, Dependency::Tmux | Dependency::Picker)
+    }
+}
+
+/// finds binary in PATH dirs (or checks the path itself if it contains `/`)
+pub fn find_binary(name: &str) -> Option<PathBuf> {
+    if name.contains('/') {
+        return Path::new(name).is_file().then(|| PathBuf::from(name));
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
+/// fails with [`Error::MissingBinary`] if any of dependencies is not installed
+/// `picker` is the picker command (`--picker` option of pfp)
+pub fn ensure_installed(dependencies: &[Dependency], picker: &str) -> Result<(), Error> {
+    for dependency in dependencies {
+        let name = binary_name(*dependency, picker);
+        if find_binary(&name).is_none() {
+            return Err(Error::MissingBinary(name));
+        }
+    }
+    Ok(())
+}
+
+/// looks up all dependencies, reports their versions and problems
+pub fn check_dependencies(picker: &str) -> Vec<DependencyStatus> {
+    [
+        Dependency::Tmux,
+        Dependency::Picker,
+        Dependency::Tree,
+        Dependency::Editor,
+    ]
+    .into_iter()
+    .map(|dependency| check_dependency(dependency, picker))
+    .collect()
+}
+
+fn check_dependency(dependency: Dependency, picker: &str) -> DependencyStatus {
+    let name = binary_name(dependency, picker);
+    let path = find_binary(&name);
+    let version = match (dependency, &path) {
+        (Dependency::Tmux, Some(path)) => version(path, "-V"),
+        // fzf-tmux is a wrapper script, version of fzf itself matters
+        (Dependency::Picker, Some(_)) if name.starts_with("fzf") => {
+            find_binary("fzf").and_then(|path| version(&path, "--version"))
+        }
+        (Dependency::Picker, Some(path)) => version(path, "--version"),
+        _ => None,
+    };
+    let problem = match (dependency, &path) {
+        (Dependency::Editor, _) if name.is_empty() => {
+            Some("EDITOR env var is not set, picked files are opened with it".to_owned())
+        }
+        (Dependency::Editor, None) => Some(format!("EDITOR={} is not found in PATH", name)),
+        (Dependency::Tmux, None) => Some(
+            "tmux is not installed, install it with your package manager (e.g. `apt install tmux`)"
+                .to_owned(),
+        ),
+        (Dependency::Picker, None) if name == "fzf" => Some(format!(
+            "fzf is not installed, install fzf >= {}.{} (https://github.com/junegunn/fzf#installation)",
+            MIN_FZF_VERSION.0, MIN_FZF_VERSION.1
+        )),
+        (Dependency::Picker, None) => Some(format!("picker {} is not found, check --picker value", name)),
+        (Dependency::Tree, None) => Some(
+            "tree is not installed, project previews in the picker are empty (e.g. `apt install tree`)"
+                .to_owned(),
+        ),
+        (Dependency::Picker, Some(_)) if name.starts_with("fzf") => {
+            match version.as_deref().and_then(parse_version) {
+                Some(found) if found < MIN_FZF_VERSION => Some(format!(
+                    "fzf {}.{} is too old, some pickers need fzf >= {}.{}, upgrade it",
+                    found.0, found.1, MIN_FZF_VERSION.0, MIN_FZF_VERSION.1
+                )),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    DependencyStatus {
+        dependency,
+        name,
+        path,
+        version,
+        problem,
+    }
+}
+
+/// binary of the dependency, $EDITOR value may contain args (e.g. `code -w`)
+fn binary_name(dependency: Dependency, picker: &str) -> String {
+    let command = match dependency {
+        Dependency::Tmux => "tmux".to_owned(),
+        Dependency::Picker => picker.to_owned(),
+        Dependency::Tree => "tree".to_owned(),
+        Dependency::Editor => std::env::var("EDITOR").unwrap_or_default(),
+    };
+    command.split_whitespace().next().unwrap_or_default().to_owned()
+}
+
+/// first line of `<binary> <flag>` output
+fn version(path: &Path, flag: &str) -> Option<String> {
+    let output = process::Command::new(path).arg(flag).output().ok()?;
+    let output = String::from_utf8_lossy(&output.stdout);
+    Some(output.lines().next()?.trim().to_owned())
+}
+
+/// major and minor version from version output like `0.44.1 (brew)` or `tmux 3.3a`
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let version = version
+        .split_whitespace()
+        .find(|word| word.starts_with(|c: char| c.is_ascii_digit()))?;
+    let mut parts = version.split('.').map(|part| {
+        part.trim_end_matches(|c: char| !c.is_ascii_digit())
+            .parse::<u32>()
+    });
+    Some((parts.next()?.ok()?, parts.next().unwrap_or(Ok(0)).ok()?))
+}
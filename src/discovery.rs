@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::fs::{expand, get_included_paths_list};
+use crate::{measure, Error};
+
+/// Project candidate found while traversing include entries
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Project {
+    /// full path of the project dir (or file, for include entries in `file` mode)
+    pub path: String,
+    /// marker found in the project dir, None for intermediate paths and files
+    pub marker: Option<String>,
+    /// index of the include entry (in `config.include`) the project was found by
+    pub include_entry: usize,
+    /// number of steps from the include entry's root path
+    pub depth: u8,
+}
+
+/// traverses all include entries of the config and returns found projects sorted by path,
+/// these are the candidates the project picker shows
+/// a path found by several include entries is reported once, preferring the entry where a marker matched
+pub fn discover_projects(config: &Config) -> Result<Vec<Project>, Error> {
+    let mut projects = HashMap::new();
+    for (index, include_entry) in config.include.iter().enumerate() {
+        for path in &include_entry.paths {
+            let expanded_path = expand(path)?;
+            if include_entry.include_intermediate_paths {
+                insert_project(
+                    &mut projects,
+                    Project {
+                        path: expanded_path.clone(),
+                        marker: None,
+                        include_entry: index,
+                        depth: 0,
+                    },
+                );
+            }
+            measure(&format!("traversal of {}", expanded_path), || {
+                get_included_paths_list(&expanded_path, 0, &mut projects, index, config)
+            })?;
+        }
+    }
+    let mut projects = projects.into_values().collect::<Vec<Project>>();
+    projects.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(projects)
+}
+
+/// adds project to the list, project already in the list is replaced only if the new one has a matched marker
+pub(crate) fn insert_project(projects: &mut HashMap<String, Project>, project: Project) {
+    match projects.get(&project.path) {
+        Some(existing) if existing.marker.is_some() || project.marker.is_none() => {}
+        _ => {
+            projects.insert(project.path.clone(), project);
+        }
+    }
+}
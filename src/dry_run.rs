@@ -0,0 +1,86 @@
+//! Dry run mode: commands that would change state (tmux commands, hooks, file system changes)
+//! are recorded instead of being run, callers read them with [`take_recorded`]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use log::debug;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+/// shell commands recorded in dry run mode, in the order they would be run
+static RECORDED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// enables dry run mode: tmux commands changing tmux state, hooks and file system changes are recorded instead of being run
+pub fn set_dry_run(enabled: bool) {
+    DRY_RUN.store(enabled, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// records shell command (args are quoted with [`shell_quote`]), returns number of commands recorded so far
+pub fn record(args: &[&str]) -> usize {
+    let command = args
+        .iter()
+        .map(|arg| shell_quote(arg))
+        .collect::<Vec<String>>()
+        .join(" ");
+    debug!("dry run: {}", command);
+    let mut recorded = RECORDED.lock().unwrap_or_else(|err| err.into_inner());
+    recorded.push(command);
+    recorded.len()
+}
+
+/// records shell comment (e.g. about waiting for something), it is not quoted
+pub(crate) fn record_comment(comment: &str) {
+    let mut recorded = RECORDED.lock().unwrap_or_else(|err| err.into_inner());
+    recorded.push(format!("# {}", comment));
+}
+
+/// returns commands recorded since the previous call
+pub fn take_recorded() -> Vec<String> {
+    std::mem::take(&mut *RECORDED.lock().unwrap_or_else(|err| err.into_inner()))
+}
+
+/// quotes arg for shell if needed, so that recorded commands can be copied and run as is
+pub fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:@%+=,".contains(c));
+    match plain {
+        true => arg.to_owned(),
+        false => format!("'{}'", arg.replace('\'', r"'\''")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shell_quote_args() {
+        for (arg, expected) in [
+            ("new-session", "new-session"),
+            ("/home/me/my.project", "/home/me/my.project"),
+            ("a=b,c:d@e%f+g", "a=b,c:d@e%f+g"),
+            ("", "''"),
+            ("two words", "'two words'"),
+            ("it's", r"'it'\''s'"),
+            ("'", r"''\'''"),
+            ("$HOME", "'$HOME'"),
+            ("#{session_id}", "'#{session_id}'"),
+            ("a\tb", "'a\tb'"),
+        ] {
+            assert_eq!(shell_quote(arg), expected, "{:?}", arg);
+            // quoted arg is read back by shell as is
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", shell_quote(arg)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout), arg, "{:?}", arg);
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::{Config, IncludeEntry};
+use crate::discovery::{insert_project, Project};
 use crate::Error;
 
 use anyhow::anyhow;
@@ -10,21 +11,20 @@ use std::env::{self, VarError};
 use std::ffi::OsStr;
 use std::fs::DirEntry;
 use std::fs::{self, FileType};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 const EMPTY_STR: &str = "";
 
 /// tries to expand env variables in string
-pub(crate) fn expand(path: &str) -> Result<String, Error> {
+pub fn expand(path: &str) -> Result<String, Error> {
     let re = Regex::new(r"\$\{?([^\}/]+)\}?")?;
     let mut errors: Vec<(VarError, String)> = Vec::new();
     let result: String = re
         .replace_all(path, |captures: &Captures| match &captures[1] {
             EMPTY_STR => EMPTY_STR.to_string(),
             varname => env::var(OsStr::new(varname))
-                .map_err(|e| {
+                .inspect_err(|e| {
                     errors.push((e.clone(), varname.to_owned()));
-                    e
                 })
                 .unwrap_or_default(),
         })
@@ -36,7 +36,7 @@ pub(crate) fn expand(path: &str) -> Result<String, Error> {
 }
 
 /// retains the tail of the path
-pub(crate) fn trim_window_name(path: &str) -> Result<String, anyhow::Error> {
+pub fn trim_window_name(path: &str) -> Result<String, Error> {
     let re = Regex::new(r"/(?P<first>[^/]+)/{1}(?P<second>[^/]+)$")?;
     let mut iter = re.captures_iter(path);
     if let Some(caps) = iter.next() {
@@ -52,24 +52,32 @@ pub(crate) fn trim_window_name(path: &str) -> Result<String, anyhow::Error> {
 
 /// removes all dots from original name string
 /// (needed because dots are displayed as underscores in session name for some reason)
-pub(crate) fn trim_session_name(name: &String) -> String {
+pub fn trim_session_name(name: &str) -> String {
     let mut s = String::from(name);
     s.retain(|x| x != '.');
     s
 }
 
-/// receives path, mutable list and config
-/// updates list with entries from the path tree that should be included
+/// receives path, mutable list, index of the include entry and config
+/// updates list (keyed by path) with entries from the path tree that should be included
 /// on intermediate steps, returns path_yields (indicates that this path yielded matches)
 /// intermediate paths are included if include_intermediate_paths = true
 pub(crate) fn get_included_paths_list(
     path: &str,
     depth: u8,
-    output: &mut HashMap<String, ()>,
-    include_entry: &IncludeEntry,
+    output: &mut HashMap<String, Project>,
+    include_index: usize,
     config: &Config,
 ) -> Result<bool, Error> {
+    let include_entry = &config.include[include_index];
     let mut path_yields = false;
+    let mut marker = None;
+    let project = |path: &str, marker: Option<String>| Project {
+        path: path.to_owned(),
+        marker,
+        include_entry: include_index,
+        depth,
+    };
 
     // read current path contents
     let read_dir = match std::fs::read_dir(path) {
@@ -92,7 +100,7 @@ pub(crate) fn get_included_paths_list(
             } else {
                 [].iter()
             });
-    let markers_exact = markers_exact_chain.copied().collect::<Vec<&str>>();
+    let markers_exact = markers_exact_chain.map(String::as_str).collect::<Vec<&str>>();
     let markers_pattern_chain =
         include_entry
             .markers
@@ -103,7 +111,7 @@ pub(crate) fn get_included_paths_list(
             } else {
                 [].iter()
             });
-    let markers_pattern = markers_pattern_chain.copied().collect::<Vec<&str>>();
+    let markers_pattern = markers_pattern_chain.map(String::as_str).collect::<Vec<&str>>();
     let markers_regex_set = RegexSet::new(markers_pattern)?;
 
     // do the thing according to chosen mode
@@ -128,16 +136,17 @@ pub(crate) fn get_included_paths_list(
                     // yield_on_marker stops descending further down the fs tree
                     path_yields = true;
                     if include_entry.yield_on_marker {
-                        output.insert(path.to_string(), ());
+                        insert_project(output, project(path, Some(name)));
                         return Ok(path_yields);
                     }
+                    marker = Some(name);
                     break;
                 }
             }
 
             if depth >= include_entry.depth {
                 if path_yields {
-                    output.insert(path.to_string(), ());
+                    insert_project(output, project(path, marker));
                 }
                 // reached maximum depth -> return
                 return Ok(path_yields);
@@ -157,14 +166,14 @@ pub(crate) fn get_included_paths_list(
             // walk current dir's children
             for child in children {
                 // if child yields matches
-                if get_included_paths_list(&child, depth + 1, output, include_entry, config)? {
+                if get_included_paths_list(&child, depth + 1, output, include_index, config)? {
                     path_yields = true;
                 };
             }
 
             // if path yields matches and we include every step of the final match, include this path
             if path_yields && include_entry.include_intermediate_paths {
-                output.insert(path.to_string(), ());
+                insert_project(output, project(path, marker));
             }
 
             Ok(path_yields)
@@ -185,7 +194,15 @@ pub(crate) fn get_included_paths_list(
                 } else if is_file(&path, &ft)? {
                     // -> add file to the list of included paths
                     path_yields = true;
-                    output.insert(path, ());
+                    insert_project(
+                        output,
+                        Project {
+                            path,
+                            marker: None,
+                            include_entry: include_index,
+                            depth: depth + 1,
+                        },
+                    );
                 }
             }
 
@@ -197,14 +214,14 @@ pub(crate) fn get_included_paths_list(
             // walk current dir's children
             for child in children {
                 // if child yields matches
-                if get_included_paths_list(&child, depth + 1, output, include_entry, config)? {
+                if get_included_paths_list(&child, depth + 1, output, include_index, config)? {
                     path_yields = true;
                 };
             }
 
             // if path yields matches and we include every step of the final match, include this path
             if path_yields && include_entry.include_intermediate_paths {
-                output.insert(path.to_string(), ());
+                insert_project(output, project(path, None));
             }
 
             Ok(path_yields)
@@ -228,7 +245,7 @@ fn get_not_ignored_dir_entries(
             } else {
                 [].iter()
             });
-    let ignore_exact = ignore_exact_chain.copied().collect::<Vec<&str>>();
+    let ignore_exact = ignore_exact_chain.map(String::as_str).collect::<Vec<&str>>();
     let ignore_pattern_chain =
         include_entry
             .ignore
@@ -239,7 +256,7 @@ fn get_not_ignored_dir_entries(
             } else {
                 [].iter()
             });
-    let ignore_pattern = ignore_pattern_chain.copied().collect::<Vec<&str>>();
+    let ignore_pattern = ignore_pattern_chain.map(String::as_str).collect::<Vec<&str>>();
     let ignore_regex_set = RegexSet::new(ignore_pattern)?;
 
     let mut result: Vec<(String, FileType)> = vec![];
@@ -322,10 +339,12 @@ fn read_link(path: &str) -> Option<std::path::PathBuf> {
     }
 }
 
-pub(crate) fn path_is_file(path: &str) -> bool {
+pub fn path_is_file(path: &str) -> bool {
     let meta = std::fs::metadata(path);
     match meta {
         Ok(meta) => meta.is_file(),
+        // path of a project dir not created in dry run mode
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound && crate::dry_run::is_dry_run() => false,
         Err(err) => {
             error!("error reading metadata of path {}: {}", path, err);
             // if getting metadata failed (e.g. due to insufficient rights), treat as dir
@@ -333,3 +352,22 @@ pub(crate) fn path_is_file(path: &str) -> bool {
         }
     }
 }
+
+/// recursively copies contents of `from` dir into `to` dir (`to` should exist)
+/// .git dir is skipped, so that templates can be kept in git repos themselves
+pub fn copy_dir(from: &Path, to: &Path) -> Result<(), std::io::Error> {
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        if entry.file_name() == ".git" {
+            continue;
+        }
+        let target = to.join(entry.file_name());
+        if entry.path().is_dir() {
+            fs::create_dir_all(&target)?;
+            copy_dir(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+use std::process;
+
+use log::trace;
+
+use crate::config::Config;
+use crate::dry_run::{is_dry_run, record};
+use crate::fs::path_is_file;
+use crate::Error;
+
+/// runs each command with `sh -c` in `cwd` dir, with `env` vars added to the environment
+/// stops at the first command that fails, in dry run mode commands are recorded instead
+pub fn run_hooks(commands: &[String], cwd: &str, env: &[(&str, &str)]) -> Result<(), Error> {
+    for command in commands {
+        trace!("run hook {} in {}", command, cwd);
+        if is_dry_run() {
+            record(&["cd", cwd]);
+            record(&["sh", "-c", command]);
+            continue;
+        }
+        let status = process::Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .current_dir(cwd)
+            .envs(env.iter().copied())
+            .status()?;
+        if !status.success() {
+            return Err(Error::Hook(format!("`{}` exited with {}", command, status)));
+        }
+    }
+    Ok(())
+}
+
+/// Lifecycle event hooks are configured for (see [`crate::config::Hooks`])
+#[derive(Debug, Clone, Copy)]
+pub enum HookEvent {
+    SessionCreate,
+    WindowCreate,
+    SessionKill,
+}
+
+/// runs hooks configured for the event (see [`Config::hooks_for`] for the order they are run in)
+pub fn run_lifecycle_hooks(
+    config: &Config,
+    event: HookEvent,
+    path: &str,
+    session_name: &str,
+    window_name: Option<&str>,
+) -> Result<(), Error> {
+    let commands = config
+        .hooks_for(path)
+        .into_iter()
+        .flat_map(|hooks| match event {
+            HookEvent::SessionCreate => hooks.on_session_create.iter(),
+            HookEvent::WindowCreate => hooks.on_window_create.iter(),
+            HookEvent::SessionKill => hooks.on_session_kill.iter(),
+        })
+        .cloned()
+        .collect::<Vec<String>>();
+    if commands.is_empty() {
+        return Ok(());
+    }
+    trace!("{:?} hooks for {}", event, path);
+
+    // hooks for a file are run in its dir
+    let cwd = match path_is_file(path) {
+        true => Path::new(path).parent().and_then(Path::to_str).unwrap_or(path),
+        false => path,
+    };
+    let mut env = vec![("PFP_PROJECT_PATH", path), ("PFP_SESSION_NAME", session_name)];
+    if let Some(window_name) = window_name {
+        env.push(("PFP_WINDOW_NAME", window_name));
+    }
+    run_hooks(&commands, cwd, &env)
+}
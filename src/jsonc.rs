@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// Locations of values in JSONC text, keyed by path of the value (e.g. `include.0.paths.1`)
+#[derive(Default)]
+pub(crate) struct Spans {
+    /// (line, column) positions, object members are mapped to positions of their keys
+    pub positions: HashMap<String, (usize, usize)>,
+    /// byte ranges of values (without keys)
+    pub values: HashMap<String, Range<usize>>,
+}
+
+/// scans JSONC text and returns locations of its values
+/// text is expected to be valid JSONC, scanning stops at the first unexpected char
+pub(crate) fn spans(text: &str) -> Spans {
+    let mut scanner = Scanner {
+        chars: text.char_indices().collect(),
+        len: text.len(),
+        pos: 0,
+        line: 1,
+        column: 1,
+        spans: Spans::default(),
+    };
+    scanner.value(String::new());
+    scanner.spans
+}
+
+pub(crate) fn join_key(parent: &str, key: &str) -> String {
+    match parent {
+        "" => key.to_owned(),
+        parent => format!("{}.{}", parent, key),
+    }
+}
+
+struct Scanner {
+    chars: Vec<(usize, char)>,
+    len: usize,
+    pos: usize,
+    line: usize,
+    column: usize,
+    spans: Spans,
+}
+
+impl Scanner {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).map(|c| c.1)
+    }
+
+    fn peek_next(&self) -> Option<char> {
+        self.chars.get(self.pos + 1).map(|c| c.1)
+    }
+
+    /// byte offset of the current char
+    fn offset(&self) -> usize {
+        self.chars.get(self.pos).map(|c| c.0).unwrap_or(self.len)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// skips whitespace and comments
+    fn skip_trivia(&mut self) {
+        loop {
+            match (self.peek(), self.peek_next()) {
+                (Some(c), _) if c.is_whitespace() => {
+                    self.bump();
+                }
+                (Some('/'), Some('/')) => while !matches!(self.bump(), None | Some('\n')) {},
+                (Some('/'), Some('*')) => {
+                    self.bump();
+                    self.bump();
+                    while self.peek().is_some() && (self.peek(), self.peek_next()) != (Some('*'), Some('/')) {
+                        self.bump();
+                    }
+                    self.bump();
+                    self.bump();
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn string(&mut self) -> String {
+        let mut s = String::new();
+        self.bump();
+        while let Some(c) = self.bump() {
+            match c {
+                '"' => break,
+                '\\' => s.extend(self.bump()),
+                c => s.push(c),
+            }
+        }
+        s
+    }
+
+    fn value(&mut self, path: String) {
+        self.skip_trivia();
+        self.spans
+            .positions
+            .entry(path.clone())
+            .or_insert((self.line, self.column));
+        let start = self.offset();
+        match self.peek() {
+            Some('{') => {
+                self.bump();
+                loop {
+                    self.skip_trivia();
+                    match self.peek() {
+                        Some('"') => {
+                            let position = (self.line, self.column);
+                            let key = join_key(&path, &self.string());
+                            self.spans.positions.insert(key.clone(), position);
+                            self.skip_trivia();
+                            if self.peek() == Some(':') {
+                                self.bump();
+                            }
+                            self.value(key);
+                        }
+                        Some(',') => {
+                            self.bump();
+                        }
+                        Some('}') => {
+                            self.bump();
+                            break;
+                        }
+                        _ => return,
+                    }
+                }
+            }
+            Some('[') => {
+                self.bump();
+                let mut index = 0;
+                loop {
+                    self.skip_trivia();
+                    match self.peek() {
+                        Some(',') => {
+                            self.bump();
+                            index += 1;
+                        }
+                        Some(']') => {
+                            self.bump();
+                            break;
+                        }
+                        Some(_) => {
+                            let pos = self.pos;
+                            self.value(join_key(&path, &index.to_string()));
+                            if self.pos == pos {
+                                return;
+                            }
+                        }
+                        None => return,
+                    }
+                }
+            }
+            Some('"') => {
+                self.string();
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|c| !c.is_whitespace() && !matches!(c, ',' | '}' | ']' | '/'))
+                {
+                    self.bump();
+                }
+            }
+        }
+        self.spans.values.insert(path, start..self.offset());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// text of the value at the path
+    fn value<'a>(text: &'a str, spans: &Spans, key: &str) -> &'a str {
+        &text[spans.values[key].clone()]
+    }
+
+    #[test]
+    fn comments_inside_arrays() {
+        let text =
+            "{\n  \"exact\": [\n    // first\n    \".git\", /* second */ \"go.mod\"\n    // end\n  ]\n}";
+        let spans = spans(text);
+        assert_eq!(value(text, &spans, "exact.0"), "\".git\"");
+        assert_eq!(value(text, &spans, "exact.1"), "\"go.mod\"");
+        assert_eq!(spans.positions["exact.1"], (4, 26));
+        assert!(value(text, &spans, "exact").ends_with("// end\n  ]"));
+        assert!(!spans.values.contains_key("exact.2"));
+    }
+
+    #[test]
+    fn escaped_quotes_in_strings() {
+        let text = r#"{"a\"b": "x\"}y", "c": [1, "\\"]}"#;
+        let spans = spans(text);
+        assert_eq!(value(text, &spans, "a\"b"), r#""x\"}y""#);
+        assert_eq!(value(text, &spans, "c.1"), r#""\\""#);
+        assert_eq!(spans.positions["c"], (1, 19));
+    }
+
+    #[test]
+    fn nested_include_entries() {
+        let text = r#"{
+  "include": [
+    { "paths": ["$HOME"], "markers": [".git"] },
+    {
+      "paths": ["/work"],
+      "ignore": { "exact": ["target"] }
+    }
+  ]
+}"#;
+        let spans = spans(text);
+        assert_eq!(value(text, &spans, "include.0.markers"), r#"[".git"]"#);
+        assert_eq!(value(text, &spans, "include.1.paths.0"), r#""/work""#);
+        assert_eq!(value(text, &spans, "include.1.ignore.exact"), r#"["target"]"#);
+        assert_eq!(spans.positions["include.1.ignore"], (6, 7));
+    }
+
+    #[test]
+    fn join_keys() {
+        for (parent, key, expected) in [("", "a", "a"), ("a", "0", "a.0"), ("a.0", "b", "a.0.b")] {
+            assert_eq!(join_key(parent, key), expected);
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Pfp finds your projects on disk and manages tmux sessions and windows for them.
+//!
+//! The `pfp` binary is built on top of this crate, the same building blocks can be reused by other tools:
+//! - [`config`]: config model, loading and merging of config layers ([`config::read_config`])
+//! - [`discovery`]: project discovery according to config ([`discovery::discover_projects`])
+//! - [`session`]: tmux session creation for projects and predefined sessions
+//!
+//! ```no_run
+//! let config = pfp::config::read_config(&["/home/me/.config/pfp/config.json".to_owned()])?;
+//! for project in pfp::discovery::discover_projects(&config)? {
+//!     println!("{} {:?}", project.path, project.marker);
+//! }
+//! # Ok::<(), pfp::Error>(())
+//! ```
+
+pub mod check;
+pub mod config;
+pub mod deps;
+pub mod discovery;
+pub mod dry_run;
+pub mod fs;
+pub mod hooks;
+mod jsonc;
+pub mod project;
+pub mod session;
+pub mod tmux;
+
+use crate::config::ConfigError;
+
+use log::info;
+
+use std::env::VarError;
+use std::string::FromUtf8Error;
+use std::time::Instant;
+
+/// Error of any pfp operation
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("Config error: {0}")]
+    Config(#[from] ConfigError),
+    #[error("Cmd arguments error: {0}")]
+    CmdArg(String),
+    #[error("Descend error: {0}")]
+    Descend(#[from] anyhow::Error),
+    #[error("IO error: {0}")]
+    IO(#[from] std::io::Error),
+    #[error("Unwrap IO stream error: {0}")]
+    UnwrapIOStream(&'static str),
+    #[error("Regex error: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("Env var error: {0}: {1}")]
+    EnvVar(VarError, String),
+    #[error("Parse utf8 error: {0}")]
+    ParseUTF8(#[from] FromUtf8Error),
+    #[error("Empty pick!")]
+    EmptyPick(),
+    #[error("Hook failed: {0}")]
+    Hook(String),
+    #[error("Project error: {0}")]
+    Project(String),
+    #[error("Tmux error: {0}")]
+    Tmux(String),
+    #[error("{0} is not installed (not found in PATH), run `pfp doctor` for details")]
+    MissingBinary(String),
+    #[error("Picker failed: {0}")]
+    Picker(String),
+    #[error("Session not ready: {0}")]
+    NotReady(String),
+}
+
+/// error of spawning external binary, missing binary is reported as [`Error::MissingBinary`]
+pub fn spawn_error(binary: &str, err: std::io::Error) -> Error {
+    match err.kind() {
+        std::io::ErrorKind::NotFound => Error::MissingBinary(binary.to_owned()),
+        _ => err.into(),
+    }
+}
+
+/// runs `f` and logs how long it took (visible with `-v`)
+pub fn measure<T, F>(name: &str, f: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    let start = Instant::now();
+    let result = f();
+    info!("Time elapsed for {} is: {:?}", name, start.elapsed());
+    result
+}
@@ -1,55 +1,34 @@
 mod cli;
-mod config;
-mod fs;
-mod fzf;
-mod selectors;
-mod tmux;
 
-use crate::config::ConfigError;
-use log::info;
+use clap::CommandFactory;
+use pfp::Error;
 
-use std::env::VarError;
-use std::string::FromUtf8Error;
-use std::time::Instant;
-
-#[derive(thiserror::Error, Debug)]
-enum Error {
-    #[error("Config error: {0}")]
-    Config(#[from] ConfigError),
-    #[error("Cmd arguments error: {0}")]
-    CmdArg(String),
-    #[error("Descend error: {0}")]
-    Descend(#[from] anyhow::Error),
-    #[error("IO error: {0}")]
-    IO(#[from] std::io::Error),
-    #[error("Unwrap IO stream error: {0}")]
-    UnwrapIOStream(&'static str),
-    #[error("Regex error: {0}")]
-    Regex(#[from] regex::Error),
-    #[error("Env var error: {0}: {1}")]
-    EnvVar(VarError, String),
-    #[error("Parse utf8 error: {0}")]
-    ParseUTF8(#[from] FromUtf8Error),
-    #[error("Empty pick!")]
-    EmptyPick(),
-}
+/// exit code of cancelled pick (same as for interrupted command)
+const EXIT_CANCELLED: i32 = 130;
 
 fn main() {
+    // answer shell completion requests (made by scripts from `pfp completions`), exits if it was one
+    clap_complete::CompleteEnv::with_factory(cli::Cli::command).complete();
     match cli::cli() {
         Ok(_) => std::process::exit(exitcode::OK),
         Err(error) => {
             eprintln!("{}", error);
-            std::process::exit(exitcode::DATAERR);
+            std::process::exit(exit_code(&error));
         }
     }
 }
 
-#[allow(dead_code)]
-pub fn measure<F>(name: &str, mut f: F)
-where
-    F: FnMut(),
-{
-    let start = Instant::now();
-    f();
-    info!("Time elapsed for {} is: {:?}", name, start.elapsed());
+/// exit code for each error category, so that scripts can tell them apart
+fn exit_code(error: &Error) -> i32 {
+    match error {
+        Error::EmptyPick() => EXIT_CANCELLED,
+        Error::Config(_) => exitcode::CONFIG,
+        Error::CmdArg(_) => exitcode::USAGE,
+        Error::MissingBinary(_) => exitcode::UNAVAILABLE,
+        Error::Tmux(_) | Error::Hook(_) | Error::Picker(_) => exitcode::SOFTWARE,
+        Error::IO(_) | Error::UnwrapIOStream(_) => exitcode::IOERR,
+        Error::NotReady(_) => exitcode::TEMPFAIL,
+        // Descend, Regex, EnvVar, ParseUTF8, Project (and errors added later)
+        _ => exitcode::DATAERR,
+    }
 }
@@ -0,0 +1,90 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use log::trace;
+use sha2::{Digest, Sha256};
+
+use crate::config::{ConfigError, ProjectConfig};
+use crate::dry_run::is_dry_run;
+use crate::fs::expand;
+use crate::Error;
+
+pub const PROJECT_CONFIG_FILE: &str = ".pfp.json";
+
+/// reads project config from the project dir, returns None if there is no project config file
+/// file contents are returned alongside the config to check whether they are trusted
+pub fn read_project_config(dir: &str) -> Result<Option<(ProjectConfig, String)>, ConfigError> {
+    let path = Path::new(dir).join(PROJECT_CONFIG_FILE);
+    if !path.is_file() {
+        return Ok(None);
+    }
+    trace!("project config {:?}", path);
+    let contents = fs::read_to_string(path)?;
+    Ok(Some((serde_jsonc::from_str(&contents)?, contents)))
+}
+
+/// resolves path declared in the project config relative to the project dir
+pub fn project_path(root: &str, path: Option<&str>) -> Result<String, Error> {
+    let path = match path {
+        Some(path) => Path::new(root).join(expand(path)?),
+        None => PathBuf::from(root),
+    };
+    Ok(path.to_string_lossy().trim_end_matches('/').to_owned())
+}
+
+/// checks whether user trusted commands from the project config file with its current contents
+/// trust is bound to file contents, so any change to the file requires new confirmation
+pub fn is_trusted(dir: &str, contents: &str) -> Result<bool, Error> {
+    let entry = trust_entry(dir, contents);
+    let trusted = fs::read_to_string(trust_store_path()?).unwrap_or_default();
+    Ok(trusted.lines().any(|line| line == entry))
+}
+
+/// records that user trusts commands from the project config file with its current contents,
+/// replacing trust in its previous contents (not recorded in dry run mode)
+pub fn trust(dir: &str, contents: &str) -> Result<(), Error> {
+    if is_dry_run() {
+        return Ok(());
+    }
+    let path = project_config_path(dir);
+    let entry = trust_entry(dir, contents);
+    let store = trust_store_path()?;
+    let trusted = fs::read_to_string(&store).unwrap_or_default();
+    let mut lines = trusted
+        .lines()
+        .filter(|line| line.split_once(' ').map(|(_, p)| p) != Some(path.as_str()))
+        .collect::<Vec<&str>>();
+    lines.push(&entry);
+    if let Some(parent) = store.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&store, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// path of the project config file in the project dir
+pub fn project_config_path(dir: &str) -> String {
+    Path::new(dir)
+        .join(PROJECT_CONFIG_FILE)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// trust store line: hash of the file contents and its path
+fn trust_entry(dir: &str, contents: &str) -> String {
+    format!(
+        "{:x} {}",
+        Sha256::digest(contents.as_bytes()),
+        project_config_path(dir)
+    )
+}
+
+/// ${XDG_DATA_HOME}/pfp/trusted, falling back to ~/.local/share if XDG_DATA_HOME is not set
+fn trust_store_path() -> Result<PathBuf, Error> {
+    let data_home = match env::var("XDG_DATA_HOME") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(expand("${HOME}/.local/share")?),
+    };
+    Ok(data_home.join("pfp").join("trusted"))
+}
@@ -0,0 +1,587 @@
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use log::{info, trace};
+
+use crate::config::{Config, ProjectConfig, Session, Window};
+use crate::dry_run::{is_dry_run, record_comment};
+use crate::fs::{expand, path_is_file, trim_session_name, trim_window_name};
+use crate::hooks::{run_lifecycle_hooks, HookEvent};
+use crate::project::{project_path, read_project_config};
+use crate::tmux::{execute_tmux_args, execute_tmux_command, execute_tmux_window_command, tmux_output};
+use crate::{measure, Error};
+
+/// pause between readiness checks of a session
+const READY_INTERVAL: Duration = Duration::from_millis(500);
+
+/// spawns detached tmux session for the project path, returns its name
+/// if project dir contains project config, session is created according to it
+/// commands from the project config are run only if `trust` (called with project dir and config file contents) returns true,
+/// e.g. asks user to confirm (see [`crate::project::is_trusted`] and [`crate::project::trust`])
+pub fn create_session<F>(config: &Config, path: &str, trust: F) -> Result<String, Error>
+where
+    F: FnOnce(&str, &str) -> Result<bool, Error>,
+{
+    let project_config = match path_is_file(path) {
+        true => None,
+        false => read_project_config(path)?,
+    };
+    let session_name = match project_config {
+        Some((project_config, contents)) => {
+            let trusted = !project_config.has_commands() || trust(path, &contents)?;
+            create_project_session(config, path, &project_config, trusted)?
+        }
+        None => {
+            let window_name = trim_window_name(path)?;
+            let session_name = trim_session_name(&window_name);
+            execute_tmux_window_command(
+                &format!(
+                    "tmux new-session -d -s {} -n {} -c {}",
+                    session_name, window_name, path
+                ),
+                path,
+            )?;
+            mark_session(&session_name)?;
+            session_name
+        }
+    };
+    run_lifecycle_hooks(config, HookEvent::SessionCreate, path, &session_name, None)?;
+    Ok(session_name)
+}
+
+/// creates session with windows and panes declared in the project config, returns session name
+/// commands are typed into panes only if `run_commands` is set
+pub fn create_project_session(
+    config: &Config,
+    root: &str,
+    project_config: &ProjectConfig,
+    run_commands: bool,
+) -> Result<String, Error> {
+    let session_name = trim_session_name(&match &project_config.session_name {
+        Some(name) => name.to_owned(),
+        None => trim_window_name(root)?,
+    });
+    let session_target = format!("{}:", session_name);
+    let env = project_config
+        .env
+        .iter()
+        .flat_map(|(k, v)| ["-e".to_owned(), format!("{}={}", k, v)])
+        .collect::<Vec<String>>();
+
+    let default_windows = [Window::default()];
+    let windows = match project_config.windows.is_empty() {
+        true => &default_windows[..],
+        false => &project_config.windows[..],
+    };
+    for (i, window) in windows.iter().enumerate() {
+        let path = project_path(root, window.path.as_deref())?;
+        let window_name = match &window.name {
+            Some(name) => name.to_owned(),
+            None => trim_window_name(&path)?,
+        };
+        let mut args = match i {
+            // create session with first window, session env is inherited by all its windows
+            0 => {
+                let mut args = vec!["new-session", "-d", "-s", &session_name];
+                args.extend(env.iter().map(String::as_str));
+                args
+            }
+            // create window in the new session
+            _ => vec!["new-window", "-d", "-t", &session_target],
+        };
+        args.extend(["-P", "-F", "#{window_id}", "-n", &window_name, "-c", &path]);
+        let window_id = tmux_output(&args)?;
+        if i == 0 {
+            mark_session(&session_name)?;
+        }
+        if let (true, Some(command)) = (run_commands, &window.command) {
+            execute_tmux_args(&["send-keys", "-t", &window_id, command, "Enter"])?;
+        }
+
+        for pane in window.panes.iter() {
+            let pane_path = project_path(&path, pane.path.as_deref())?;
+            let pane_id = tmux_output(&[
+                "split-window",
+                "-d",
+                "-P",
+                "-F",
+                "#{pane_id}",
+                "-t",
+                &window_id,
+                "-c",
+                &pane_path,
+            ])?;
+            if let (true, Some(command)) = (run_commands, &pane.command) {
+                execute_tmux_args(&["send-keys", "-t", &pane_id, command, "Enter"])?;
+            }
+        }
+        if let Some(layout) = &window.layout {
+            tmux_output(&["select-layout", "-t", &window_id, layout])?;
+        }
+
+        if i > 0 {
+            run_lifecycle_hooks(
+                config,
+                HookEvent::WindowCreate,
+                &path,
+                &session_name,
+                Some(&window_name),
+            )?;
+        }
+    }
+    Ok(session_name)
+}
+
+/// creates detached tmux session from the predefined session config, with a window for each of its paths
+pub fn start_session(config: &Config, session: &Session) -> Result<(), Error> {
+    let iter = session.windows.iter();
+    for (i, window) in iter.enumerate() {
+        let path = &expand(window.trim_end_matches('/'))?;
+        let window_name = trim_window_name(path)?;
+        let cmd = &match i {
+            // create session with first window
+            0 => format!(
+                "tmux new-session -d -s {} -n {} -c {}",
+                session.name, window_name, path,
+            ),
+            // create window in current session
+            _ => format!("tmux new-window -d -n {} -P -F '#S:#I' -c {}", window_name, path,),
+        };
+        let mut window = String::from_utf8(execute_tmux_window_command(cmd, path)?.stdout)?;
+
+        // move consequent windows to new session
+        if i > 0 {
+            window.retain(|x| x != '\'' && x != '\n');
+            execute_tmux_command(&format!("tmux move-window -s {} -t {}:", window, session.name))?;
+        }
+
+        if i == 0 {
+            mark_session(&session.name)?;
+        }
+        match i {
+            0 => run_lifecycle_hooks(config, HookEvent::SessionCreate, path, &session.name, None)?,
+            _ => run_lifecycle_hooks(
+                config,
+                HookEvent::WindowCreate,
+                path,
+                &session.name,
+                Some(&window_name),
+            )?,
+        }
+    }
+    // renumber windows with no-op move
+    execute_tmux_command(&format!(
+        "tmux movew -r -s {}:1 -t {}:1",
+        session.name, session.name
+    ))?;
+    Ok(())
+}
+
+/// Changes made by [`reconcile_session`]
+#[derive(Debug, Default)]
+pub struct Reconciled {
+    /// windows (name and path) declared in config that were missing and have been added
+    pub added: Vec<(String, String)>,
+    /// names of windows not declared in config, they are kept as is
+    pub extra: Vec<String>,
+}
+
+/// adds windows of the predefined session missing in the running session, windows already there are not touched
+/// window is considered present if it has the same name or its active pane is in the window's path
+pub fn reconcile_session(config: &Config, session: &Session) -> Result<Reconciled, Error> {
+    let output = tmux_output(&[
+        "list-windows",
+        "-t",
+        &session.name,
+        "-F",
+        "#{window_name}\t#{pane_current_path}",
+    ])?;
+    let existing = output
+        .lines()
+        .map(|line| line.split_once('\t').unwrap_or((line, "")))
+        .collect::<Vec<(&str, &str)>>();
+    let mut reconciled = Reconciled::default();
+    let mut matched = vec![];
+    for window in session.windows.iter() {
+        let path = expand(window.trim_end_matches('/'))?;
+        let window_name = trim_window_name(&path)?;
+        if let Some(i) = existing
+            .iter()
+            .position(|(name, current)| *name == window_name || *current == path)
+        {
+            matched.push(i);
+            continue;
+        }
+        execute_tmux_window_command(
+            &format!(
+                "tmux new-window -d -t {}: -n {} -c {}",
+                session.name, window_name, path
+            ),
+            &path,
+        )?;
+        run_lifecycle_hooks(
+            config,
+            HookEvent::WindowCreate,
+            &path,
+            &session.name,
+            Some(&window_name),
+        )?;
+        reconciled.added.push((window_name, path));
+    }
+    reconciled.extra = existing
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !matched.contains(i))
+        .map(|(_, (name, _))| name.to_string())
+        .collect();
+    Ok(reconciled)
+}
+
+/// waits until readiness checks of the session pass, fails if they don't pass in time
+/// in dry run mode checks are recorded (as shell comment) instead
+pub fn wait_ready(session: &Session) -> Result<(), Error> {
+    let Some(ready) = &session.ready else {
+        return Ok(());
+    };
+    if is_dry_run() {
+        record_comment(&format!("wait for {}: {}", session.name, ready));
+        return Ok(());
+    }
+    let mut dir = match session.windows.first() {
+        Some(window) => expand(window.trim_end_matches('/'))?,
+        None => expand("$HOME")?,
+    };
+    // windows opening a file are started in its dir
+    if Path::new(&dir).is_file() {
+        dir = Path::new(&dir)
+            .parent()
+            .and_then(Path::to_str)
+            .unwrap_or("/")
+            .to_owned();
+    }
+    let file = ready.file.as_deref().map(expand).transpose()?;
+    let deadline = Instant::now() + Duration::from_secs(ready.timeout);
+    measure(&format!("wait for {}", session.name), || loop {
+        let port_open = ready.port.is_none_or(|port| {
+            (ready.host.as_str(), port)
+                .to_socket_addrs()
+                .into_iter()
+                .flatten()
+                .any(|addr| TcpStream::connect_timeout(&addr, Duration::from_secs(1)).is_ok())
+        });
+        let file_exists = file.as_ref().is_none_or(|file| Path::new(file).exists());
+        let is_ready = port_open
+            && file_exists
+            && ready.command.as_ref().is_none_or(|command| {
+                process::Command::new("sh")
+                    .args(["-c", command])
+                    .current_dir(&dir)
+                    .stdin(process::Stdio::null())
+                    .stdout(process::Stdio::null())
+                    .stderr(process::Stdio::null())
+                    .status()
+                    .is_ok_and(|status| status.success())
+            });
+        if is_ready {
+            return Ok(());
+        }
+        if Instant::now() >= deadline {
+            return Err(Error::NotReady(format!(
+                "{} is not ready after {}s, waited for {}",
+                session.name, ready.timeout, ready
+            )));
+        }
+        trace!("session {} is not ready yet", session.name);
+        thread::sleep(READY_INTERVAL);
+    })
+}
+
+/// creates window for the path in the current session (of the client pfp is run from), returns window name
+pub fn create_window(config: &Config, path: &str) -> Result<String, Error> {
+    let window_name = trim_window_name(path)?;
+    execute_tmux_window_command(&format!("tmux new-window -n {} -c {}", window_name, path), path)?;
+    let session_name = tmux_output(&["display-message", "-p", "#S"])?;
+    run_lifecycle_hooks(
+        config,
+        HookEvent::WindowCreate,
+        path,
+        &session_name,
+        Some(&window_name),
+    )?;
+    Ok(window_name)
+}
+
+/// session option marking sessions created by pfp (`prune` treats them as disposable)
+const PFP_OPTION: &str = "@pfp";
+
+fn mark_session(session_name: &str) -> Result<(), Error> {
+    tmux_output(&["set-option", "-t", session_name, PFP_OPTION, "1"])?;
+    Ok(())
+}
+
+/// switches tmux client to the first window of the session
+pub fn switch_client(session_name: &str) -> Result<(), Error> {
+    execute_tmux_args(&["switch-client", "-t", &format!("{}:1", session_name)])?;
+    Ok(())
+}
+
+/// Running tmux session
+#[derive(Debug, Clone)]
+pub struct SessionInfo {
+    /// session id (e.g. `$1`), unlike name it is always a valid target
+    pub id: String,
+    pub name: String,
+    pub windows: usize,
+    /// number of attached clients
+    pub attached: usize,
+    /// time of the last activity, seconds since unix epoch
+    pub activity: u64,
+    /// whether session was created by pfp
+    pub managed: bool,
+    /// session's working dir
+    pub path: String,
+}
+
+/// lists running tmux sessions ordered by creation (session id)
+pub fn list_sessions() -> Result<Vec<SessionInfo>, Error> {
+    let output = tmux_output(&[
+        "list-sessions",
+        "-F",
+        "#{session_id}\t#{session_name}\t#{session_windows}\t#{session_attached}\t#{session_activity}\t#{@pfp}\t#{session_path}",
+    ])?;
+    let mut sessions = output
+        .lines()
+        .map(|line| {
+            let fields = line.splitn(7, '\t').collect::<Vec<&str>>();
+            match fields.as_slice() {
+                [id, name, windows, attached, activity, managed, path] => Ok(SessionInfo {
+                    id: id.to_string(),
+                    name: name.to_string(),
+                    windows: windows.parse().unwrap_or_default(),
+                    attached: attached.parse().unwrap_or_default(),
+                    activity: activity.parse().unwrap_or_default(),
+                    managed: !managed.is_empty(),
+                    path: path.to_string(),
+                }),
+                _ => Err(Error::Tmux(format!("unexpected list-sessions output: {}", line))),
+            }
+        })
+        .collect::<Result<Vec<SessionInfo>, Error>>()?;
+    sessions.sort_by_key(|s| s.id.trim_start_matches('$').parse::<u64>().unwrap_or(u64::MAX));
+    Ok(sessions)
+}
+
+/// Window or pane of a running tmux session
+#[derive(Debug, Clone)]
+pub struct TargetInfo {
+    /// window id (e.g. `@1`) or pane id (e.g. `%1`)
+    pub id: String,
+    /// human readable target, `session:window` for windows and `session:window.pane` for panes
+    pub target: String,
+    /// window name
+    pub name: String,
+    /// working dir of the pane (of the active pane for windows)
+    pub path: String,
+    /// command running in the pane (in the active pane for windows)
+    pub command: String,
+}
+
+/// lists windows of all running sessions
+pub fn list_windows() -> Result<Vec<TargetInfo>, Error> {
+    list_targets("list-windows", "#{window_id}\t#S:#I")
+}
+
+/// lists panes of all running sessions
+pub fn list_panes() -> Result<Vec<TargetInfo>, Error> {
+    list_targets("list-panes", "#{pane_id}\t#S:#I.#P")
+}
+
+fn list_targets(command: &str, format: &str) -> Result<Vec<TargetInfo>, Error> {
+    let format = format!(
+        "{}\t#{{window_name}}\t#{{pane_current_path}}\t#{{pane_current_command}}",
+        format
+    );
+    let output = tmux_output(&[command, "-a", "-F", &format])?;
+    output
+        .lines()
+        .map(|line| {
+            let fields = line.splitn(5, '\t').collect::<Vec<&str>>();
+            match fields.as_slice() {
+                [id, target, name, path, command] => Ok(TargetInfo {
+                    id: id.to_string(),
+                    target: target.to_string(),
+                    name: name.to_string(),
+                    path: path.to_string(),
+                    command: command.to_string(),
+                }),
+                _ => Err(Error::Tmux(format!("unexpected {} output: {}", command, line))),
+            }
+        })
+        .collect()
+}
+
+/// switches tmux client to the window or pane (given by id) and selects it
+pub fn switch_to_target(id: &str) -> Result<(), Error> {
+    tmux_output(&["switch-client", "-t", id])?;
+    match id.starts_with('%') {
+        true => tmux_output(&["select-pane", "-t", id])?,
+        false => tmux_output(&["select-window", "-t", id])?,
+    };
+    Ok(())
+}
+
+/// commands considered idle when running in a pane
+pub const SHELLS: [&str; 9] = ["sh", "bash", "zsh", "fish", "dash", "ksh", "tcsh", "csh", "nu"];
+
+/// ids and commands of panes of the session (given by id)
+fn pane_commands(id: &str) -> Result<Vec<(String, String)>, Error> {
+    let output = tmux_output(&[
+        "list-panes",
+        "-s",
+        "-t",
+        id,
+        "-F",
+        "#{pane_id}\t#{pane_current_command}",
+    ])?;
+    Ok(output
+        .lines()
+        .map(|line| {
+            let (pane, command) = line.split_once('\t').unwrap_or(("", line));
+            (pane.to_owned(), command.to_owned())
+        })
+        .collect())
+}
+
+fn is_shell(command: &str) -> bool {
+    // login shells are reported with leading dash
+    SHELLS.contains(&command.trim_start_matches('-'))
+}
+
+/// commands other than shells running in panes of the session (given by id), without duplicates
+/// `skip_pane` (e.g. the pane pfp itself is run in) is left out
+pub fn busy_commands(id: &str, skip_pane: Option<&str>) -> Result<Vec<String>, Error> {
+    let mut commands = vec![];
+    for (pane, command) in pane_commands(id)? {
+        if Some(pane.as_str()) != skip_pane && !is_shell(&command) && !commands.contains(&command) {
+            commands.push(command);
+        }
+    }
+    Ok(commands)
+}
+
+/// Why session is considered stale by [`stale_sessions`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StaleReason {
+    /// session's working dir no longer exists
+    MissingPath,
+    /// no activity for the given number of hours
+    Idle(u64),
+    /// session was created by pfp, is not attached and has a single pane running a shell
+    IdleShell,
+}
+
+impl std::fmt::Display for StaleReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StaleReason::MissingPath => write!(f, "project dir does not exist"),
+            StaleReason::Idle(hours) => write!(f, "idle for {}h", hours),
+            StaleReason::IdleShell => write!(f, "only an idle shell"),
+        }
+    }
+}
+
+/// finds sessions that can be killed: with missing working dir, detached with no activity for `idle_hours`
+/// (0 disables the check), or created by pfp and left detached with an idle shell
+pub fn stale_sessions(idle_hours: u64) -> Result<Vec<(SessionInfo, StaleReason)>, Error> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let mut stale = vec![];
+    for session in list_sessions()? {
+        let idle = now.saturating_sub(session.activity) / 3600;
+        let reason = if !Path::new(&session.path).is_dir() {
+            StaleReason::MissingPath
+        // attached session is in use even if nothing happens in it (e.g. a client left open overnight)
+        } else if idle_hours > 0 && idle >= idle_hours && session.attached == 0 {
+            StaleReason::Idle(idle)
+        } else if session.managed
+            && session.attached == 0
+            && matches!(pane_commands(&session.id)?.as_slice(), [(_, command)] if is_shell(command))
+        {
+            StaleReason::IdleShell
+        } else {
+            continue;
+        };
+        stale.push((session, reason));
+    }
+    Ok(stale)
+}
+
+/// runs `on_session_kill` hooks (if session dir still exists) and kills the session (given by id)
+pub fn kill_session(config: &Config, id: &str) -> Result<(), Error> {
+    let session = tmux_output(&["display-message", "-p", "-t", id, "#S\t#{session_path}"])?;
+    let (name, path) = session.split_once('\t').unwrap_or((&session, ""));
+    // hooks are run in the session dir, they can't be run if it was deleted
+    match Path::new(path).exists() {
+        true => run_lifecycle_hooks(config, HookEvent::SessionKill, path, name, None)?,
+        false => info!(
+            "session {} dir {} does not exist, skipping on_session_kill hooks",
+            name, path
+        ),
+    }
+    tmux_output(&["kill-session", "-t", id])?;
+    Ok(())
+}
+
+/// kills sessions (given by ids) with [`kill_session`], client is switched to another session first if its session is killed
+pub fn kill_sessions(config: &Config, ids: &[&str]) -> Result<(), Error> {
+    // client is detached when its session is killed, switch it to another session first
+    if std::env::var_os("TMUX").is_some() {
+        switch_away_from(ids)?;
+    }
+    for id in ids {
+        kill_session(config, id)?;
+    }
+    Ok(())
+}
+
+/// switches tmux client away from sessions that are going to be killed, so that it stays attached:
+/// to the last session, or to the most recently active one, or to a new session if no other sessions are left
+fn switch_away_from(killed: &[&str]) -> Result<(), Error> {
+    let client = tmux_output(&[
+        "display-message",
+        "-p",
+        "#{client_name}\t#{session_id}\t#{client_last_session}",
+    ])?;
+    let [client, current, last] = client.splitn(3, '\t').collect::<Vec<&str>>()[..] else {
+        return Ok(());
+    };
+    // nothing to switch if session has no client attached (e.g. pfp is run in a detached session)
+    if client.is_empty() || !killed.contains(&current) {
+        return Ok(());
+    }
+    let mut sessions = list_sessions()?;
+    sessions.retain(|s| !killed.contains(&s.id.as_str()));
+    sessions.sort_by_key(|s| std::cmp::Reverse(s.activity));
+    let target = match sessions.iter().find(|s| s.name == last || s.id == last) {
+        Some(session) => session.id.to_owned(),
+        None => match sessions.first() {
+            Some(session) => session.id.to_owned(),
+            None => tmux_output(&[
+                "new-session",
+                "-d",
+                "-P",
+                "-F",
+                "#{session_id}",
+                "-c",
+                &expand("$HOME")?,
+            ])?,
+        },
+    };
+    tmux_output(&["switch-client", "-t", &target])?;
+    Ok(())
+}
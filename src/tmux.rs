@@ -1,23 +1,98 @@
+use std::os::unix::process::ExitStatusExt;
 use std::process;
 
+use crate::dry_run::{is_dry_run, record};
 use crate::fs::{expand, path_is_file};
+use crate::{measure, spawn_error, Error};
+
+use log::debug;
+
+/// tmux commands that only query tmux state, these are run even in dry run mode
+const QUERY_COMMANDS: [&str; 9] = [
+    "display-message",
+    "list-sessions",
+    "list-windows",
+    "list-panes",
+    "list-clients",
+    "show-options",
+    "show-environment",
+    "has-session",
+    "capture-pane",
+];
+
+/// every tmux call goes through here
+/// in dry run mode commands that change tmux state are recorded and reported as successful,
+/// commands printing created object (`-P`) output a placeholder, so it can be used as a target in next commands
+fn run_tmux<'a>(
+    args: impl Iterator<Item = &'a str> + Clone,
+    stdin: process::Stdio,
+) -> Result<process::Output, Error> {
+    let command = args.clone().next().unwrap_or_default();
+    if !is_dry_run() || QUERY_COMMANDS.contains(&command) {
+        debug!("tmux {}", args.clone().collect::<Vec<&str>>().join(" "));
+        return measure(&format!("tmux {}", command), || {
+            let mut tmux = process::Command::new("tmux");
+            // tmux replaces tabs and non-ASCII chars in output of non-UTF-8 clients (e.g. LANG unset in cron)
+            if QUERY_COMMANDS.contains(&command) {
+                tmux.arg("-u");
+            }
+            tmux.stdin(stdin)
+                .args(args)
+                .output()
+                .map_err(|err| spawn_error("tmux", err))
+        });
+    }
+    let n = record(&std::iter::once("tmux").chain(args.clone()).collect::<Vec<&str>>());
+    let stdout = match args.clone().any(|arg| arg == "-P") {
+        true => format!("<{}:{}>\n", command, n),
+        false => String::new(),
+    };
+    Ok(process::Output {
+        status: process::ExitStatus::from_raw(0),
+        stdout: stdout.into_bytes(),
+        stderr: vec![],
+    })
+}
 
 pub(crate) fn execute_tmux_command_with_stdin(
     cmd: &str,
     stdin: process::Stdio,
-) -> std::io::Result<process::Output> {
+) -> Result<process::Output, Error> {
     let args = cmd.split(' ').skip(1);
-    process::Command::new("tmux").stdin(stdin).args(args).output()
+    run_tmux(args, stdin)
 }
 
-pub(crate) fn execute_tmux_command(cmd: &str) -> std::io::Result<process::Output> {
+pub(crate) fn execute_tmux_command(cmd: &str) -> Result<process::Output, Error> {
     execute_tmux_command_with_stdin(cmd, process::Stdio::piped())
 }
 
-/// Executes tmux new-window/new-session with shell-command depending on target filetype. 
+/// Executes tmux with args passed as is (use it if args may contain spaces, e.g. paths or shell commands)
+pub fn execute_tmux_args(args: &[&str]) -> Result<process::Output, Error> {
+    execute_tmux_args_with_stdin(args, process::Stdio::piped())
+}
+
+/// Executes tmux with args passed as is and the given stdin (inherit it to attach to a session)
+pub fn execute_tmux_args_with_stdin(args: &[&str], stdin: process::Stdio) -> Result<process::Output, Error> {
+    run_tmux(args.iter().copied(), stdin)
+}
+
+/// Executes tmux with args passed as is and returns its trimmed stdout, fails if tmux exits with error
+pub fn tmux_output(args: &[&str]) -> Result<String, Error> {
+    let output = execute_tmux_args(args)?;
+    if !output.status.success() {
+        return Err(Error::Tmux(format!(
+            "{}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim_end()
+        )));
+    }
+    Ok(String::from_utf8(output.stdout)?.trim_end().to_owned())
+}
+
+/// Executes tmux new-window/new-session with shell-command depending on target filetype.
 /// If target is a file, launches this file in $EDITOR instead of just opening path in new window.
 /// IMPORTANT: '-c' flag (specifying working directory for the window) should be placed at the end of the command, as we want to trim filename from that path.
-pub(crate) fn execute_tmux_window_command(cmd: &str, target: &str) -> Result<process::Output, anyhow::Error> {
+pub(crate) fn execute_tmux_window_command(cmd: &str, target: &str) -> Result<process::Output, Error> {
     if path_is_file(target) {
         let split = cmd.split('/');
         Ok(execute_tmux_command_with_stdin(